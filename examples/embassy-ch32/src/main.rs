@@ -70,7 +70,7 @@ async fn main(spawner: Spawner) {
         }
     }
 
-    bmp180.init().unwrap();
+    let bmp180 = bmp180.init().unwrap();
     println!("Device init");
 
     spawner.spawn(print_temperature_pressure(bmp180)).unwrap();
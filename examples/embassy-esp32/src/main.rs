@@ -1,8 +1,12 @@
 #![no_std]
 #![no_main]
 
-use bmp085_180_rs::BMP;
+use bmp085_180_rs::logic::calculate_pressure_altitude;
+use bmp085_180_rs::sampler::{Sampler, SamplerConfig, SamplerControl};
+use bmp085_180_rs::{Measurement, BMP};
 use embassy_executor::Spawner;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
 use embassy_time::{Delay, Timer};
 use esp_backtrace as _;
 use esp_hal::{
@@ -16,19 +20,24 @@ use esp_hal::{
     Async,
 };
 
+static CONTROL: SamplerControl<CriticalSectionRawMutex> =
+    SamplerControl::new(SamplerConfig::DEFAULT);
+static MEASUREMENTS: Channel<CriticalSectionRawMutex, Measurement, 4> = Channel::new();
+
 #[embassy_executor::task]
-async fn print_temperature_pressure(mut bmp180: BMP<I2C<'static, I2C0, Async>, Delay>) {
-    loop {
-        let temp = bmp180.read_temperature().await.unwrap();
-        esp_println::println!("Temperature: {} ºC", temp);
+async fn sample(bmp180: BMP<I2C<'static, I2C0, Async>, Delay>) {
+    Sampler::new(bmp180).run(&CONTROL, &MEASUREMENTS).await
+}
 
-        let pres = bmp180.read_pressure().await.unwrap();
-        esp_println::println!("Pressure: {} Pa", pres);
+#[embassy_executor::task]
+async fn print_temperature_pressure() {
+    loop {
+        let measurement = MEASUREMENTS.receive().await;
+        esp_println::println!("Temperature: {} ºC", measurement.temperature);
+        esp_println::println!("Pressure: {} Pa", measurement.pressure);
 
-        let alt = bmp180.read_altitude().await.unwrap();
+        let alt = calculate_pressure_altitude(measurement.pressure);
         esp_println::println!("Altitude: {} m", alt);
-
-        Timer::after_secs(1).await;
     }
 }
 
@@ -70,9 +79,10 @@ async fn main(spawner: Spawner) {
         }
     }
 
-    bmp180.init().await.unwrap();
+    let bmp180 = bmp180.init().await.unwrap();
     esp_println::println!("Device init");
 
-    spawner.spawn(print_temperature_pressure(bmp180)).unwrap();
+    spawner.spawn(sample(bmp180)).unwrap();
+    spawner.spawn(print_temperature_pressure()).unwrap();
     spawner.spawn(long_running()).unwrap();
 }
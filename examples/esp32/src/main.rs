@@ -36,7 +36,7 @@ fn main() -> ! {
         }
     }
 
-    bmp180.init().unwrap();
+    let mut bmp180 = bmp180.init().unwrap();
     log::info!("Device init");
 
     delay.delay(500.millis());
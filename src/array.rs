@@ -0,0 +1,240 @@
+//! Several BMP085/BMP180 devices measuring at the same time.
+
+use crate::types::*;
+
+#[cfg(not(feature = "async"))]
+use embedded_hal::delay::DelayNs;
+#[cfg(not(feature = "async"))]
+use embedded_hal::i2c::I2c;
+#[cfg(feature = "async")]
+use embedded_hal_async::delay::DelayNs as AsyncDelayNs;
+#[cfg(feature = "async")]
+use embedded_hal_async::i2c::I2c as AsyncI2c;
+
+/// Driver for `N` BMP085/BMP180 devices, e.g. each on its own channel of a TCA9548A/PCA9548 multiplexer
+/// with `mux::MuxChannel` (`mux` feature):
+///
+/// ```ignore
+/// let mux = Tca9548a::new(i2c, 0x70);
+/// let mut array = BmpArray::init([
+///     BMP::new(mux.channel(2), delay, Default::default()),
+///     BMP::new(mux.channel(5), delay, Default::default()),
+/// ])?;
+/// let [indoor, outdoor] = array.read();
+/// ```
+///
+/// Conversions are started on every device before waiting for them to complete, so that all readings
+/// are taken at the same time, and the wait is shared by all devices. Each device is then read as by its own
+/// [`BMP`] driver, following its [`ConversionTiming`], [`RetryPolicy`] and stuck-sensor detection, and
+/// updating its [`health`](BMP::health) and [`transaction_stats`](BMP::transaction_stats).
+pub struct BmpArray<I2C, D, const N: usize> {
+    drivers: [BMP<I2C, D>; N],
+}
+
+/// Uncompensated temperature and pressure of each device, or the error it failed with.
+type Readings<E, const N: usize> = [Result<(u16, u32), BMPError<E>>; N];
+
+/// Conversion time in µs of `operation` with the given timing.
+fn conversion_us(timing: &ConversionTiming, operation: Operation, oss: Oss) -> u32 {
+    match operation {
+        Operation::Pressure => timing.pressure_us(oss),
+        _ => timing.temperature_us,
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(
+        cfg(not(feature = "async")),
+        self = "BmpArray",
+        idents(AsyncI2c(sync = "I2c"), AsyncDelayNs(sync = "DelayNs"))
+    ),
+    async(feature = "async", keep_self)
+)]
+impl<I2C, D, const N: usize> BmpArray<I2C, D, N>
+where
+    I2C: AsyncI2c,
+    D: AsyncDelayNs,
+{
+    /// Creates a new [`BmpArray`](BmpArray) from initialized drivers.
+    ///
+    /// ### Arguments
+    ///
+    /// * `drivers` - Initialized [`BMP`] driver of each device.
+    pub fn new(drivers: [BMP<I2C, D>; N]) -> Self {
+        BmpArray { drivers }
+    }
+
+    /// Check the connection to every device, then initialize and calibrate their drivers.
+    ///
+    /// ### Arguments
+    ///
+    /// * `drivers` - Uninitialized [`BMP`] driver of each device.
+    ///
+    /// ### Returns
+    ///
+    /// The driver, ready to take measurements. Fails with the first error of a device, e.g.
+    /// [`InvalidDeviceId`](BMPError::InvalidDeviceId) if another kind of device answered.
+    pub async fn init(
+        drivers: [BMP<I2C, D, Uninitialized>; N],
+    ) -> Result<Self, BMPError<I2C::Error>> {
+        let mut initialized: [Option<BMP<I2C, D>>; N] = core::array::from_fn(|_| None);
+        for (mut driver, slot) in drivers.into_iter().zip(initialized.iter_mut()) {
+            driver.test_connection().await?;
+            *slot = Some(driver.init().await?);
+        }
+
+        Ok(BmpArray {
+            drivers: initialized.map(|driver| driver.unwrap()),
+        })
+    }
+
+    /// Waits for the conversions started on every device that hasn't failed yet to complete.
+    ///
+    /// The longest configured conversion time is waited once for all devices, then the SCO bit of each
+    /// device is polled if enabled, for up to the rest of its worst-case conversion time.
+    async fn wait_for_conversions(
+        &mut self,
+        operation: Operation,
+        readings: &mut Readings<I2C::Error, N>,
+    ) {
+        let waited_us = self
+            .drivers
+            .iter()
+            .map(|driver| conversion_us(&driver.timing, operation, driver.oss))
+            .max()
+            .unwrap_or(0);
+        if let Some(driver) = self.drivers.first_mut() {
+            driver.delayer.delay_us(waited_us).await;
+        }
+
+        for (driver, reading) in self.drivers.iter_mut().zip(readings.iter_mut()) {
+            if reading.is_err() {
+                continue;
+            }
+            let max_us = conversion_us(&ConversionTiming::DATASHEET_MAX, operation, driver.oss);
+            if let Err(err) = driver
+                .wait_for_conversion(operation, 0, max_us.saturating_sub(waited_us))
+                .await
+            {
+                *reading = Err(err);
+            }
+        }
+    }
+
+    /// Measure temperature and pressure on every device, at the same time.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// Compensated [`Measurement`](Measurement) of each device, in the order of the drivers.
+    /// A failure on one device doesn't affect the others' measurements.
+    pub async fn read(&mut self) -> [Result<Measurement, BMPError<I2C::Error>>; N] {
+        let mut readings: Readings<I2C::Error, N> = core::array::from_fn(|_| Ok((0, 0)));
+
+        for (driver, reading) in self.drivers.iter_mut().zip(readings.iter_mut()) {
+            if let Err(err) = driver.start_temperature().await {
+                *reading = Err(err);
+            }
+        }
+        self.wait_for_conversions(Operation::Temperature, &mut readings)
+            .await;
+        for (driver, reading) in self.drivers.iter_mut().zip(readings.iter_mut()) {
+            if let Ok((ut, _)) = reading {
+                match driver.finish_temperature().await {
+                    Ok(value) => *ut = value,
+                    Err(err) => *reading = Err(err),
+                }
+            }
+        }
+
+        for (driver, reading) in self.drivers.iter_mut().zip(readings.iter_mut()) {
+            if reading.is_ok() {
+                if let Err(err) = driver.start_pressure().await {
+                    *reading = Err(err);
+                }
+            }
+        }
+        self.wait_for_conversions(Operation::Pressure, &mut readings)
+            .await;
+        for (driver, reading) in self.drivers.iter_mut().zip(readings.iter_mut()) {
+            if let Ok((_, up)) = reading {
+                match driver.finish_pressure().await {
+                    Ok(value) => *up = value,
+                    Err(err) => *reading = Err(err),
+                }
+            }
+        }
+
+        let mut results = self
+            .drivers
+            .iter_mut()
+            .zip(readings)
+            .map(|(driver, reading)| {
+                let result = reading.and_then(|(ut, up)| {
+                    driver.compensate_sample(RawSample {
+                        ut,
+                        up,
+                        oss: driver.oss,
+                    })
+                });
+                driver.record_health(&result);
+                result
+            });
+        core::array::from_fn(|_| results.next().unwrap())
+    }
+
+    /// Get the calibration data read from each device during initialization.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// [`CalibrationData`](CalibrationData) of each device, in the order of the drivers
+    pub fn calibration_data(&self) -> [CalibrationData; N] {
+        core::array::from_fn(|index| self.drivers[index].calibration_data())
+    }
+
+    /// Get the driver of each device, e.g. to check its [`health`](BMP::health).
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// The [`BMP`] drivers, in the order they were given in
+    pub fn drivers(&self) -> &[BMP<I2C, D>; N] {
+        &self.drivers
+    }
+
+    /// Get the driver of each device mutably, e.g. to measure with a single device.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// The [`BMP`] drivers, in the order they were given in
+    pub fn drivers_mut(&mut self) -> &mut [BMP<I2C, D>; N] {
+        &mut self.drivers
+    }
+
+    /// Release the drivers.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// The [`BMP`] drivers, in the order they were given in
+    pub fn release(self) -> [BMP<I2C, D>; N] {
+        self.drivers
+    }
+}
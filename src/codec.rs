@@ -0,0 +1,337 @@
+//! Compact binary encodings of measurements, for storage or transmission, e.g. over LoRa.
+//!
+//! ### Record format
+//!
+//! Every record starts with the [`FORMAT_VERSION`] and a record type, followed by big-endian fields:
+//!
+//! | Record | Type | Fields | Length |
+//! | ------ | ---- | ------ | ------ |
+//! | Measurement | `0x01` | temperature in 0.1 ºC (`i16`), pressure in Pa (`u24`) | 7 bytes |
+//! | Calibration | `0x02` | calibration registers `0xAA` to `0xBF`, as read from the device | 24 bytes |
+//!
+//! Decoders reject records of other versions, so that the format may evolve.
+//!
+//! ### CayenneLPP
+//!
+//! [`encode_cayenne_lpp`] writes a measurement as CayenneLPP temperature and barometer channels,
+//! which most LoRaWAN network servers decode out of the box.
+
+use core::fmt::{Display, Formatter};
+
+use crate::types::{CalibrationData, Measurement};
+
+/// Version of the record format, the first byte of every record.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Length in bytes of an encoded measurement record.
+pub const MEASUREMENT_LEN: usize = 7;
+
+/// Length in bytes of an encoded calibration record.
+pub const CALIBRATION_LEN: usize = 24;
+
+/// Length in bytes of a measurement encoded as CayenneLPP.
+pub const CAYENNE_LPP_LEN: usize = 8;
+
+const MEASUREMENT_TYPE: u8 = 0x01;
+const CALIBRATION_TYPE: u8 = 0x02;
+
+const LPP_TEMPERATURE: u8 = 0x67;
+const LPP_BAROMETER: u8 = 0x73;
+
+/// Largest pressure in Pa a measurement record holds.
+const MAX_PRESSURE: i32 = 0xFF_FFFF;
+
+/// Errors encoding or decoding records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CodecError {
+    /// Buffer is shorter than the record
+    BufferTooShort,
+    /// Record was encoded with another version of the format
+    UnsupportedVersion(u8),
+    /// Record is of another type than the one decoded
+    UnexpectedRecordType(u8),
+    /// Measurement doesn't fit in the fields of the record
+    ValueOutOfRange,
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CodecError::BufferTooShort => write!(f, "Buffer too short for record"),
+            CodecError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported record format version {version}")
+            }
+            CodecError::UnexpectedRecordType(record_type) => {
+                write!(f, "Unexpected record type 0x{record_type:02X}")
+            }
+            CodecError::ValueOutOfRange => write!(f, "Value out of range of record field"),
+        }
+    }
+}
+
+impl core::error::Error for CodecError {}
+
+/// Temperature in 0.1 ºC, if it fits in an `i16`.
+fn decicelsius(temperature: f32) -> Result<i16, CodecError> {
+    let value = libm::roundf(temperature * 10.0);
+    if value >= i16::MIN as f32 && value <= i16::MAX as f32 {
+        Ok(value as i16)
+    } else {
+        Err(CodecError::ValueOutOfRange)
+    }
+}
+
+/// Get the `len` bytes of a record of `record_type`, checking its header.
+fn record(bytes: &[u8], record_type: u8, len: usize) -> Result<&[u8], CodecError> {
+    let record = bytes.get(..len).ok_or(CodecError::BufferTooShort)?;
+    if record[0] != FORMAT_VERSION {
+        return Err(CodecError::UnsupportedVersion(record[0]));
+    }
+    if record[1] != record_type {
+        return Err(CodecError::UnexpectedRecordType(record[1]));
+    }
+    Ok(&record[2..])
+}
+
+/// Encode a measurement record.
+///
+/// ### Arguments
+///
+/// * `measurement` - The [`Measurement`] to encode, with a temperature rounded to 0.1 ºC.
+/// * `buffer` - Buffer of at least [`MEASUREMENT_LEN`] bytes.
+///
+/// ### Returns
+///
+/// Number of bytes written
+pub fn encode_measurement(
+    measurement: &Measurement,
+    buffer: &mut [u8],
+) -> Result<usize, CodecError> {
+    let temperature = decicelsius(measurement.temperature)?;
+    if !(0..=MAX_PRESSURE).contains(&measurement.pressure) {
+        return Err(CodecError::ValueOutOfRange);
+    }
+    let record = buffer
+        .get_mut(..MEASUREMENT_LEN)
+        .ok_or(CodecError::BufferTooShort)?;
+
+    record[0] = FORMAT_VERSION;
+    record[1] = MEASUREMENT_TYPE;
+    record[2..4].copy_from_slice(&temperature.to_be_bytes());
+    record[4..7].copy_from_slice(&measurement.pressure.to_be_bytes()[1..]);
+    Ok(MEASUREMENT_LEN)
+}
+
+/// Decode a measurement record.
+///
+/// ### Arguments
+///
+/// * `bytes` - Record encoded by [`encode_measurement`], trailing bytes are ignored.
+///
+/// ### Returns
+///
+/// The decoded [`Measurement`]
+pub fn decode_measurement(bytes: &[u8]) -> Result<Measurement, CodecError> {
+    let fields = record(bytes, MEASUREMENT_TYPE, MEASUREMENT_LEN)?;
+    let temperature = i16::from_be_bytes([fields[0], fields[1]]);
+    let pressure = i32::from_be_bytes([0, fields[2], fields[3], fields[4]]);
+
+    Ok(Measurement {
+        temperature: temperature as f32 / 10.0,
+        pressure,
+    })
+}
+
+/// Encode a calibration record, e.g. to compensate raw readings elsewhere.
+///
+/// ### Arguments
+///
+/// * `calib_data` - The [`CalibrationData`] to encode.
+/// * `buffer` - Buffer of at least [`CALIBRATION_LEN`] bytes.
+///
+/// ### Returns
+///
+/// Number of bytes written
+pub fn encode_calibration(
+    calib_data: &CalibrationData,
+    buffer: &mut [u8],
+) -> Result<usize, CodecError> {
+    let record = buffer
+        .get_mut(..CALIBRATION_LEN)
+        .ok_or(CodecError::BufferTooShort)?;
+
+    record[0] = FORMAT_VERSION;
+    record[1] = CALIBRATION_TYPE;
+    record[2..].copy_from_slice(&calib_data.to_be_bytes());
+    Ok(CALIBRATION_LEN)
+}
+
+/// Decode a calibration record.
+///
+/// ### Arguments
+///
+/// * `bytes` - Record encoded by [`encode_calibration`], trailing bytes are ignored.
+///
+/// ### Returns
+///
+/// The decoded [`CalibrationData`]
+pub fn decode_calibration(bytes: &[u8]) -> Result<CalibrationData, CodecError> {
+    let fields = record(bytes, CALIBRATION_TYPE, CALIBRATION_LEN)?;
+    let mut registers = [0; 22];
+    registers.copy_from_slice(fields);
+
+    Ok(CalibrationData::from_be_bytes(&registers))
+}
+
+/// Encode a measurement as a CayenneLPP temperature channel, followed by a barometer channel.
+///
+/// ### Arguments
+///
+/// * `measurement` - The [`Measurement`] to encode, rounded to 0.1 ºC and 0.1 hPa.
+/// * `temperature_channel` - CayenneLPP channel of the temperature.
+/// * `pressure_channel` - CayenneLPP channel of the pressure.
+/// * `buffer` - Buffer of at least [`CAYENNE_LPP_LEN`] bytes.
+///
+/// ### Returns
+///
+/// Number of bytes written
+pub fn encode_cayenne_lpp(
+    measurement: &Measurement,
+    temperature_channel: u8,
+    pressure_channel: u8,
+    buffer: &mut [u8],
+) -> Result<usize, CodecError> {
+    let temperature = decicelsius(measurement.temperature)?;
+    let pressure = u16::try_from(measurement.pressure.saturating_add(5) / 10)
+        .map_err(|_| CodecError::ValueOutOfRange)?;
+    let payload = buffer
+        .get_mut(..CAYENNE_LPP_LEN)
+        .ok_or(CodecError::BufferTooShort)?;
+
+    payload[0] = temperature_channel;
+    payload[1] = LPP_TEMPERATURE;
+    payload[2..4].copy_from_slice(&temperature.to_be_bytes());
+    payload[4] = pressure_channel;
+    payload[5] = LPP_BAROMETER;
+    payload[6..8].copy_from_slice(&pressure.to_be_bytes());
+    Ok(CAYENNE_LPP_LEN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEASUREMENT: Measurement = Measurement {
+        temperature: 15.0,
+        pressure: 69_964,
+    };
+
+    const CALIB_DATA: CalibrationData = CalibrationData {
+        ac1: 408,
+        ac2: -72,
+        ac3: -14383,
+        ac4: 32741,
+        ac5: 32757,
+        ac6: 23153,
+        b1: 6190,
+        b2: 4,
+        mb: -32768,
+        mc: -8711,
+        md: 2868,
+    };
+
+    #[test]
+    fn encodes_measurement_record() {
+        let mut buffer = [0; 16];
+
+        assert_eq!(encode_measurement(&MEASUREMENT, &mut buffer), Ok(7));
+        assert_eq!(buffer[..7], [0x01, 0x01, 0x00, 0x96, 0x01, 0x11, 0x4C]);
+    }
+
+    #[test]
+    fn measurement_round_trips() {
+        let mut buffer = [0; MEASUREMENT_LEN];
+        for measurement in [
+            MEASUREMENT,
+            Measurement {
+                temperature: -40.3,
+                pressure: 30_000,
+            },
+            Measurement {
+                temperature: 85.0,
+                pressure: 110_000,
+            },
+        ] {
+            encode_measurement(&measurement, &mut buffer).unwrap();
+            assert_eq!(decode_measurement(&buffer), Ok(measurement));
+        }
+    }
+
+    #[test]
+    fn calibration_round_trips() {
+        let mut buffer = [0; CALIBRATION_LEN];
+
+        assert_eq!(encode_calibration(&CALIB_DATA, &mut buffer), Ok(24));
+        assert_eq!(buffer[..4], [0x01, 0x02, 0x01, 0x98]);
+        assert_eq!(decode_calibration(&buffer), Ok(CALIB_DATA));
+    }
+
+    #[test]
+    fn rejects_invalid_records() {
+        let mut buffer = [0; CALIBRATION_LEN];
+        encode_measurement(&MEASUREMENT, &mut buffer).unwrap();
+
+        assert_eq!(
+            decode_calibration(&buffer),
+            Err(CodecError::UnexpectedRecordType(0x01))
+        );
+        assert_eq!(
+            decode_measurement(&buffer[..6]),
+            Err(CodecError::BufferTooShort)
+        );
+        buffer[0] = 2;
+        assert_eq!(
+            decode_measurement(&buffer),
+            Err(CodecError::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn rejects_unencodable_measurements() {
+        let mut buffer = [0; MEASUREMENT_LEN];
+
+        assert_eq!(
+            encode_measurement(&MEASUREMENT, &mut buffer[..6]),
+            Err(CodecError::BufferTooShort)
+        );
+        assert_eq!(
+            encode_measurement(
+                &Measurement {
+                    temperature: 15.0,
+                    pressure: -1
+                },
+                &mut buffer
+            ),
+            Err(CodecError::ValueOutOfRange)
+        );
+        assert_eq!(
+            encode_measurement(
+                &Measurement {
+                    temperature: f32::NAN,
+                    pressure: 69_964
+                },
+                &mut buffer
+            ),
+            Err(CodecError::ValueOutOfRange)
+        );
+    }
+
+    #[test]
+    fn encodes_cayenne_lpp() {
+        let mut buffer = [0; CAYENNE_LPP_LEN];
+
+        assert_eq!(encode_cayenne_lpp(&MEASUREMENT, 1, 2, &mut buffer), Ok(8));
+        // 15.0 ºC and 699.6 hPa
+        assert_eq!(buffer, [0x01, 0x67, 0x00, 0x96, 0x02, 0x73, 0x1B, 0x54]);
+    }
+}
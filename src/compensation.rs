@@ -0,0 +1,50 @@
+//! Compensation of uncompensated readings, as described in the datasheet.
+//!
+//! Nothing in this module depends on an I2C bus, so readings recorded with
+//! [`BMP::read_raw_pressure`](crate::BMP::read_raw_pressure), e.g. on a logger, can be compensated anywhere
+//! else, such as on a server, given the calibration registers of the device they were taken on:
+//!
+//! ```
+//! use bmp085_180_rs::compensation::{compensate, parse_calibration};
+//! use bmp085_180_rs::{Oss, RawSample};
+//!
+//! let registers = [
+//!     0x01, 0x98, 0xFF, 0xB8, 0xC7, 0xD1, 0x7F, 0xE5, 0x7F, 0xF5, 0x5A, 0x71, 0x18, 0x2E, 0x00, 0x04,
+//!     0x80, 0x00, 0xDD, 0xF9, 0x0B, 0x34,
+//! ];
+//! let calib_data = parse_calibration(&registers).unwrap();
+//! let sample = RawSample { ut: 27898, up: 23843, oss: Oss::LowPower };
+//!
+//! assert_eq!(compensate(&calib_data, sample).unwrap().pressure, 69964);
+//! ```
+
+pub use crate::logic::{
+    calculate_altitude, calculate_pressure, calculate_temperature, compensate, compensate_float,
+    compensate_with,
+};
+pub use crate::types::{
+    CalibrationData, CompensationAlgorithm, CompensationError, Measurement, Oss, RawSample,
+};
+
+/// Number of calibration registers, `0xAA` to `0xBF`.
+pub const CALIBRATION_REGISTERS: usize = 22;
+
+/// Parse calibration data from the contents of the calibration registers.
+///
+/// ### Arguments
+///
+/// * `registers` - Calibration registers `0xAA` to `0xBF`, as read from the device.
+///
+/// ### Returns
+///
+/// The [`CalibrationData`], or [`CompensationError::InvalidCalibration`] if the coefficients are
+/// not [valid](CalibrationData::is_valid)
+pub fn parse_calibration(
+    registers: &[u8; CALIBRATION_REGISTERS],
+) -> Result<CalibrationData, CompensationError> {
+    let calib_data = CalibrationData::from_be_bytes(registers);
+    if !calib_data.is_valid() {
+        return Err(CompensationError::InvalidCalibration);
+    }
+    Ok(calib_data)
+}
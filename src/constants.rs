@@ -23,8 +23,28 @@ pub const BMP_MC_LSB_REG: u8 = 0xBD;
 pub const BMP_MD_MSB_REG: u8 = 0xBE;
 pub const BMP_MD_LSB_REG: u8 = 0xBF;
 pub const BMP_CTRL_MEAS_REG: u8 = 0xF4;
+pub const BMP_CTRL_MEAS_SCO_BIT: u8 = 1 << 5;
 pub const BMP_OUT_MSB_REG: u8 = 0xF6;
 pub const BMP_OUT_LSB_REG: u8 = 0xF7;
 pub const BMP_OUT_XLSB_REG: u8 = 0xF8;
 pub const BMP_SOFT_RST_REG: u8 = 0xE0;
 pub const DEFAULT_SEA_LEVEL_PESSURE: i32 = 101_325;
+pub const BMP_DEVICE_ID: u8 = 0x55;
+/// Start-up time after a reset, in µs.
+pub const BMP_STARTUP_TIME_US: u32 = 10_000;
+/// Largest difference in pressure between oversampling settings considered consistent, in Pa.
+pub const SELF_TEST_MAX_PRESSURE_SPREAD: i32 = 100;
+/// Largest difference in temperature between measurements considered consistent, in ºC.
+pub const SELF_TEST_MAX_TEMPERATURE_SPREAD: f32 = 1.0;
+/// Standard gravity, in m/s².
+pub const STANDARD_GRAVITY: f32 = 9.806_65;
+/// Specific gas constant of dry air, in J/(kg·K).
+pub const DRY_AIR_GAS_CONSTANT: f32 = 287.05;
+/// Temperature lapse rate of the standard atmosphere, in K/m.
+pub const LAPSE_RATE: f32 = 0.0065;
+/// Offset between degrees Celsius and kelvins.
+pub const ZERO_CELSIUS: f32 = 273.15;
+/// Temperature of the standard atmosphere at sea level, in K.
+pub const ISA_SEA_LEVEL_TEMPERATURE: f32 = 288.15;
+/// Air density of the standard atmosphere at sea level, in kg/m³.
+pub const ISA_SEA_LEVEL_DENSITY: f32 = 1.225;
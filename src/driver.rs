@@ -1,6 +1,8 @@
 use crate::constants::*;
+use crate::logger::MeasurementLog;
 use crate::logic;
 use crate::types::*;
+use core::marker::PhantomData;
 
 #[cfg(not(feature = "async"))]
 use embedded_hal::delay::DelayNs;
@@ -19,42 +21,79 @@ use embedded_hal_async::i2c::I2c as AsyncI2c;
     ),
     async(feature = "async", keep_self)
 )]
-impl<I2C, D> BMP<I2C, D>
+impl<I2C, D, S> BMP<I2C, D, S>
 where
     I2C: AsyncI2c,
     D: AsyncDelayNs,
 {
-    /// Creates a new [`BMP`](BMP) driver instance, valid for both the BMP085 and BMP180 modules.
-    ///
-    /// ### Arguments
-    ///
-    /// * `i2c` - A properly initialized/configured `embedded-hal` I2C peripheral.
-    /// * `delayer` - `embedded-hal` delay for your chip.
-    /// * `config` - Driver's initial [`configuration`](Config).
-    ///
-    /// ### Example
+    /// Updates the transaction counters after an attempt of a register access and, if the attempt
+    /// failed and the retry policy allows for another one, waits for the backoff time.
     ///
-    /// ```ignore
-    /// let i2c = I2C::new(peripherals.I2C1, 100.kHz());
-    /// let delay = Delay::new();
+    /// ### Returns
     ///
-    /// let mut my_bmp = BMP::new(i2c, delay, Default::default());
-    /// ```
-    pub fn new(i2c: I2C, delayer: D, config: Config) -> Self {
-        Self {
-            i2c,
-            delayer,
-            address: config.address,
-            calib_data: CalibrationData::default(),
-            oss: config.oss,
-            sea_level_pressure: config.sea_level_pressure,
+    /// `true` if the register access should be attempted again
+    async fn retry_transaction<E>(&mut self, result: &Result<(), E>, attempt: u8) -> bool {
+        match result {
+            Ok(()) => {
+                if attempt > 1 {
+                    self.stats.retried = self.stats.retried.saturating_add(1);
+                }
+                false
+            }
+            Err(_) if attempt >= self.retry.max_attempts => {
+                self.stats.failed = self.stats.failed.saturating_add(1);
+                false
+            }
+            Err(_) => {
+                self.delayer.delay_us(self.retry.backoff_us(attempt)).await;
+                true
+            }
+        }
+    }
+
+    async fn write_register(
+        &mut self,
+        operation: Operation,
+        reg: u8,
+        value: u8,
+    ) -> Result<(), BMPError<I2C::Error>> {
+        let mut attempt = 1;
+        loop {
+            let result = self.i2c.write(self.address, &[reg, value]).await;
+            if !self.retry_transaction(&result, attempt).await {
+                return result.map_err(|source| BMPError::I2C {
+                    operation,
+                    register: reg,
+                    source,
+                });
+            }
+            attempt += 1;
         }
     }
 
-    async fn read_id(&mut self) -> Result<u8, I2C::Error> {
+    async fn read_register(
+        &mut self,
+        operation: Operation,
+        reg: u8,
+        rx: &mut [u8],
+    ) -> Result<(), BMPError<I2C::Error>> {
+        let mut attempt = 1;
+        loop {
+            let result = self.i2c.write_read(self.address, &[reg], rx).await;
+            if !self.retry_transaction(&result, attempt).await {
+                return result.map_err(|source| BMPError::I2C {
+                    operation,
+                    register: reg,
+                    source,
+                });
+            }
+            attempt += 1;
+        }
+    }
+
+    async fn read_id(&mut self) -> Result<u8, BMPError<I2C::Error>> {
         let mut id = [0];
-        self.i2c
-            .write_read(self.address, &[BMP_ID_REG], &mut id)
+        self.read_register(Operation::Connect, BMP_ID_REG, &mut id)
             .await?;
         Ok(id[0])
     }
@@ -69,30 +108,107 @@ where
     ///
     /// `Ok` if the device was detected and validated, `Err(msg)` otherwise, with `msg` containing more information.
     pub async fn test_connection(&mut self) -> Result<(), BMPError<I2C::Error>> {
-        match self.read_id().await {
-            Ok(0x55) => Ok(()),
-            Err(i2c_err) => Err(BMPError::I2C(i2c_err)),
-            _ => Err(BMPError::InvalidDeviceId),
+        match self.read_id().await? {
+            BMP_DEVICE_ID => Ok(()),
+            id => Err(BMPError::InvalidDeviceId(id)),
         }
     }
 
     #[inline(always)]
     async fn read16_i2c(
         &mut self,
+        operation: Operation,
         reg_h: u8,
         reg_l: u8,
         rx: &mut [u8; 2],
-    ) -> Result<u16, I2C::Error> {
-        self.i2c
-            .write_read(self.address, &[reg_h], &mut rx[0..1])
-            .await?;
-        self.i2c
-            .write_read(self.address, &[reg_l], &mut rx[1..2])
-            .await?;
+    ) -> Result<u16, BMPError<I2C::Error>> {
+        self.read_register(operation, reg_h, &mut rx[0..1]).await?;
+        self.read_register(operation, reg_l, &mut rx[1..2]).await?;
         Ok(((rx[0] as u16) << 8) | (rx[1] as u16))
     }
 
-    /// Initialize and calibrate the driver.
+    /// Trigger a soft reset of the BMP device. It will perform the same sequence as power on reset.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// None
+    pub async fn soft_reset(&mut self) -> Result<(), BMPError<I2C::Error>> {
+        self.write_register(Operation::Reset, BMP_SOFT_RST_REG, 0xB6)
+            .await
+    }
+
+    /// Get the counters of register accesses that had to be retried, or failed despite retrying.
+    /// See [`RetryPolicy`](RetryPolicy).
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// The driver's [`TransactionStats`](TransactionStats)
+    pub fn transaction_stats(&self) -> TransactionStats {
+        self.stats
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(
+        cfg(not(feature = "async")),
+        self = "BMP",
+        idents(AsyncI2c(sync = "I2c"), AsyncDelayNs(sync = "DelayNs"))
+    ),
+    async(feature = "async", keep_self)
+)]
+impl<I2C, D> BMP<I2C, D, Uninitialized>
+where
+    I2C: AsyncI2c,
+    D: AsyncDelayNs,
+{
+    /// Creates a new [`BMP`](BMP) driver instance, valid for both the BMP085 and BMP180 modules.
+    /// The driver must be initialized with [`init`](BMP::init) before taking measurements.
+    ///
+    /// ### Arguments
+    ///
+    /// * `i2c` - A properly initialized/configured `embedded-hal` I2C peripheral.
+    /// * `delayer` - `embedded-hal` delay for your chip.
+    /// * `config` - Driver's initial [`configuration`](Config).
+    ///
+    /// ### Example
+    ///
+    /// ```ignore
+    /// let i2c = I2C::new(peripherals.I2C1, 100.kHz());
+    /// let delay = Delay::new();
+    ///
+    /// let my_bmp = BMP::new(i2c, delay, Default::default()).init()?;
+    /// ```
+    pub fn new(i2c: I2C, delayer: D, config: Config) -> Self {
+        Self {
+            i2c,
+            delayer,
+            address: config.address,
+            calib_data: CalibrationData::default(),
+            oss: config.oss,
+            sea_level_pressure: config.sea_level_pressure,
+            timing: config.timing,
+            retry: config.retry,
+            stats: TransactionStats::default(),
+            limits: config.limits,
+            stuck_threshold: config.stuck_threshold,
+            compensation: config.compensation,
+            clock: None,
+            health: Health::default(),
+            ut_run: RepeatRun::default(),
+            up_run: RepeatRun::default(),
+            _state: PhantomData,
+        }
+    }
+
+    /// Initialize and calibrate the driver, consuming the uninitialized driver.
     ///
     /// ### Arguments
     ///
@@ -100,93 +216,313 @@ where
     ///
     /// ### Returns
     ///
-    /// `Ok` if the device was properly initialized
-    pub async fn init(&mut self) -> Result<(), BMPError<I2C::Error>> {
+    /// The initialized driver, ready to take measurements. Fails with [`InvalidConfig`](BMPError::InvalidConfig)
+    /// if the [`Config`] is invalid
+    pub async fn init(mut self) -> Result<BMP<I2C, D>, BMPError<I2C::Error>> {
+        // A single reading is always seen once in a row, a threshold below 2 would fail every measurement
+        if self.stuck_threshold.is_some_and(|threshold| threshold < 2) {
+            return Err(BMPError::InvalidConfig);
+        }
+
         let mut rx: [u8; 2] = [0, 0];
 
         self.calib_data.ac1 = self
-            .read16_i2c(BMP_AC1_MSB_REG, BMP_AC1_LSB_REG, &mut rx)
+            .read16_i2c(Operation::Init, BMP_AC1_MSB_REG, BMP_AC1_LSB_REG, &mut rx)
             .await? as i16;
         self.calib_data.ac2 = self
-            .read16_i2c(BMP_AC2_MSB_REG, BMP_AC2_LSB_REG, &mut rx)
+            .read16_i2c(Operation::Init, BMP_AC2_MSB_REG, BMP_AC2_LSB_REG, &mut rx)
             .await? as i16;
         self.calib_data.ac3 = self
-            .read16_i2c(BMP_AC3_MSB_REG, BMP_AC3_LSB_REG, &mut rx)
+            .read16_i2c(Operation::Init, BMP_AC3_MSB_REG, BMP_AC3_LSB_REG, &mut rx)
             .await? as i16;
         self.calib_data.ac4 = self
-            .read16_i2c(BMP_AC4_MSB_REG, BMP_AC4_LSB_REG, &mut rx)
+            .read16_i2c(Operation::Init, BMP_AC4_MSB_REG, BMP_AC4_LSB_REG, &mut rx)
             .await?;
         self.calib_data.ac5 = self
-            .read16_i2c(BMP_AC5_MSB_REG, BMP_AC5_LSB_REG, &mut rx)
+            .read16_i2c(Operation::Init, BMP_AC5_MSB_REG, BMP_AC5_LSB_REG, &mut rx)
             .await?;
         self.calib_data.ac6 = self
-            .read16_i2c(BMP_AC6_MSB_REG, BMP_AC6_LSB_REG, &mut rx)
+            .read16_i2c(Operation::Init, BMP_AC6_MSB_REG, BMP_AC6_LSB_REG, &mut rx)
             .await?;
         self.calib_data.b1 = self
-            .read16_i2c(BMP_B1_MSB_REG, BMP_B1_LSB_REG, &mut rx)
+            .read16_i2c(Operation::Init, BMP_B1_MSB_REG, BMP_B1_LSB_REG, &mut rx)
             .await? as i16;
         self.calib_data.b2 = self
-            .read16_i2c(BMP_B2_MSB_REG, BMP_B2_LSB_REG, &mut rx)
+            .read16_i2c(Operation::Init, BMP_B2_MSB_REG, BMP_B2_LSB_REG, &mut rx)
             .await? as i16;
         self.calib_data.mb = self
-            .read16_i2c(BMP_MB_MSB_REG, BMP_MB_LSB_REG, &mut rx)
+            .read16_i2c(Operation::Init, BMP_MB_MSB_REG, BMP_MB_LSB_REG, &mut rx)
             .await? as i16;
         self.calib_data.mc = self
-            .read16_i2c(BMP_MC_MSB_REG, BMP_MC_LSB_REG, &mut rx)
+            .read16_i2c(Operation::Init, BMP_MC_MSB_REG, BMP_MC_LSB_REG, &mut rx)
             .await? as i16;
         self.calib_data.md = self
-            .read16_i2c(BMP_MD_MSB_REG, BMP_MD_LSB_REG, &mut rx)
+            .read16_i2c(Operation::Init, BMP_MD_MSB_REG, BMP_MD_LSB_REG, &mut rx)
             .await? as i16;
 
-        Ok(())
+        Ok(BMP {
+            i2c: self.i2c,
+            delayer: self.delayer,
+            address: self.address,
+            calib_data: self.calib_data,
+            oss: self.oss,
+            sea_level_pressure: self.sea_level_pressure,
+            timing: self.timing,
+            retry: self.retry,
+            stats: self.stats,
+            limits: self.limits,
+            stuck_threshold: self.stuck_threshold,
+            compensation: self.compensation,
+            clock: self.clock,
+            health: self.health,
+            ut_run: self.ut_run,
+            up_run: self.up_run,
+            _state: PhantomData,
+        })
     }
+}
 
-    async fn read_uncompensated_temperature(&mut self) -> Result<i32, I2C::Error> {
-        let mut rx: [u8; 2] = [0, 0];
-
-        self.i2c
-            .write(self.address, &[BMP_CTRL_MEAS_REG, 0x2E])
-            .await?;
-        self.delayer.delay_ms(5).await;
+#[maybe_async_cfg::maybe(
+    sync(
+        cfg(not(feature = "async")),
+        self = "BMP",
+        idents(AsyncI2c(sync = "I2c"), AsyncDelayNs(sync = "DelayNs"))
+    ),
+    async(feature = "async", keep_self)
+)]
+impl<I2C, D> BMP<I2C, D>
+where
+    I2C: AsyncI2c,
+    D: AsyncDelayNs,
+{
+    /// Waits `wait_us` for a conversion to complete. If SCO polling is enabled, then polls the SCO bit
+    /// until it clears, for up to `max_us` or `wait_us` in total, whichever is longer.
+    pub(crate) async fn wait_for_conversion(
+        &mut self,
+        operation: Operation,
+        wait_us: u32,
+        max_us: u32,
+    ) -> Result<(), BMPError<I2C::Error>> {
+        self.delayer.delay_us(wait_us).await;
+        let Some(interval_us) = self.timing.sco_poll_interval_us else {
+            return Ok(());
+        };
 
-        Ok(self
-            .read16_i2c(BMP_OUT_MSB_REG, BMP_OUT_LSB_REG, &mut rx)
-            .await? as i32)
+        let interval_us = interval_us.max(1);
+        let max_us = max_us.max(wait_us);
+        let mut waited_us = wait_us;
+        let mut ctrl_meas = [0];
+        loop {
+            self.read_register(operation, BMP_CTRL_MEAS_REG, &mut ctrl_meas)
+                .await?;
+            if ctrl_meas[0] & BMP_CTRL_MEAS_SCO_BIT == 0 {
+                return Ok(());
+            }
+            if waited_us >= max_us {
+                return Err(BMPError::ConversionTimeout(operation));
+            }
+            let delay_us = interval_us.min(max_us - waited_us);
+            self.delayer.delay_us(delay_us).await;
+            waited_us += delay_us;
+        }
     }
 
-    async fn read_uncompensated_pressure(&mut self) -> Result<i32, I2C::Error> {
-        let mut rx_buffer: [u8; 4] = [0; 4];
+    /// Starts a temperature conversion, to be read with [`finish_temperature`](BMP::finish_temperature)
+    /// once complete.
+    pub(crate) async fn start_temperature(&mut self) -> Result<(), BMPError<I2C::Error>> {
+        self.write_register(Operation::Temperature, BMP_CTRL_MEAS_REG, 0x2E)
+            .await
+    }
 
-        self.i2c
-            .write(
-                self.address,
-                &[BMP_CTRL_MEAS_REG, 0x34 + (self.oss.val() << 6)],
+    /// Reads the uncompensated temperature (UT) of a completed conversion.
+    pub(crate) async fn finish_temperature(&mut self) -> Result<u16, BMPError<I2C::Error>> {
+        let mut rx: [u8; 2] = [0, 0];
+        let ut = self
+            .read16_i2c(
+                Operation::Temperature,
+                BMP_OUT_MSB_REG,
+                BMP_OUT_LSB_REG,
+                &mut rx,
             )
             .await?;
-        self.delayer
-            .delay_us(match self.oss.val() {
-                0 => 4500,
-                1 => 7500,
-                2 => 13_000,
-                3 => 25_500,
-                _ => 25_500, // Value shouldn't be outside of range
-            })
-            .await;
-
-        self.i2c
-            .write_read(self.address, &[BMP_OUT_MSB_REG], &mut rx_buffer[1..2])
+        let repeats = self.ut_run.push(ut);
+        self.check_stuck(repeats, Operation::Temperature)?;
+
+        Ok(ut)
+    }
+
+    /// Starts a pressure conversion with the driver's oversampling setting, to be read with
+    /// [`finish_pressure`](BMP::finish_pressure) once complete.
+    pub(crate) async fn start_pressure(&mut self) -> Result<(), BMPError<I2C::Error>> {
+        self.write_register(
+            Operation::Pressure,
+            BMP_CTRL_MEAS_REG,
+            0x34 + (self.oss.val() << 6),
+        )
+        .await
+    }
+
+    /// Reads the uncompensated pressure (UP) of a completed conversion.
+    pub(crate) async fn finish_pressure(&mut self) -> Result<u32, BMPError<I2C::Error>> {
+        let mut rx_buffer: [u8; 4] = [0; 4];
+        self.read_register(Operation::Pressure, BMP_OUT_MSB_REG, &mut rx_buffer[1..2])
             .await?;
-        self.i2c
-            .write_read(self.address, &[BMP_OUT_LSB_REG], &mut rx_buffer[2..3])
+        self.read_register(Operation::Pressure, BMP_OUT_LSB_REG, &mut rx_buffer[2..3])
             .await?;
-        self.i2c
-            .write_read(self.address, &[BMP_OUT_XLSB_REG], &mut rx_buffer[3..4])
+        self.read_register(Operation::Pressure, BMP_OUT_XLSB_REG, &mut rx_buffer[3..4])
             .await?;
-        let up = i32::from_be_bytes(rx_buffer) >> (8 - self.oss.val());
+        let up = u32::from_be_bytes(rx_buffer) >> (8 - self.oss.val());
+        let repeats = self.up_run.push(up);
+        self.check_stuck(repeats, Operation::Pressure)?;
 
         Ok(up)
     }
 
+    async fn read_uncompensated_temperature(&mut self) -> Result<u16, BMPError<I2C::Error>> {
+        self.start_temperature().await?;
+        self.wait_for_conversion(
+            Operation::Temperature,
+            self.timing.temperature_us,
+            ConversionTiming::DATASHEET_MAX.temperature_us,
+        )
+        .await?;
+        self.finish_temperature().await
+    }
+
+    async fn read_uncompensated_pressure(&mut self) -> Result<u32, BMPError<I2C::Error>> {
+        self.start_pressure().await?;
+        self.wait_for_conversion(
+            Operation::Pressure,
+            self.timing.pressure_us(self.oss),
+            ConversionTiming::DATASHEET_MAX.pressure_us(self.oss),
+        )
+        .await?;
+        self.finish_pressure().await
+    }
+
+    /// Updates the stuck state after a reading that was seen `repeats` times in a row.
+    fn check_stuck(
+        &mut self,
+        repeats: u16,
+        operation: Operation,
+    ) -> Result<(), BMPError<I2C::Error>> {
+        let Some(threshold) = self.stuck_threshold else {
+            return Ok(());
+        };
+
+        self.health.stuck = self.ut_run.count() >= threshold || self.up_run.count() >= threshold;
+        if repeats >= threshold {
+            return Err(BMPError::StuckSensor(operation));
+        }
+        Ok(())
+    }
+
+    async fn read_sample(&mut self) -> Result<RawSample, BMPError<I2C::Error>> {
+        let ut = self.read_uncompensated_temperature().await?;
+        let up = self.read_uncompensated_pressure().await?;
+
+        Ok(RawSample {
+            ut,
+            up,
+            oss: self.oss,
+        })
+    }
+
+    pub(crate) fn record_health<T>(&mut self, result: &Result<T, BMPError<I2C::Error>>) {
+        let now_ms = self.clock.map(|clock| clock());
+        self.health.record(result, now_ms);
+    }
+
+    async fn measure_temperature(&mut self) -> Result<f32, BMPError<I2C::Error>> {
+        let ut = self.read_uncompensated_temperature().await?;
+        let temperature =
+            logic::calculate_temperature_with(self.compensation, &self.calib_data, ut as i32)
+                .map_err(BMPError::Compensation)?;
+        if !self.limits.contains_temperature(temperature) {
+            return Err(BMPError::OutOfRange(Operation::Temperature));
+        }
+
+        Ok(temperature)
+    }
+
+    /// Compensates `sample`, checking the result against the plausibility limits.
+    pub(crate) fn compensate_sample(
+        &self,
+        sample: RawSample,
+    ) -> Result<Measurement, BMPError<I2C::Error>> {
+        let measurement = logic::compensate_with(self.compensation, &self.calib_data, sample)
+            .map_err(BMPError::Compensation)?;
+        if !self.limits.contains_temperature(measurement.temperature) {
+            return Err(BMPError::OutOfRange(Operation::Temperature));
+        }
+        if !self.limits.contains_pressure(measurement.pressure) {
+            return Err(BMPError::OutOfRange(Operation::Pressure));
+        }
+
+        Ok(measurement)
+    }
+
+    async fn measure(&mut self) -> Result<Measurement, BMPError<I2C::Error>> {
+        let sample = self.read_sample().await?;
+        self.compensate_sample(sample)
+    }
+
+    async fn measure_pressure(&mut self) -> Result<i32, BMPError<I2C::Error>> {
+        Ok(self.measure().await?.pressure)
+    }
+
+    async fn measure_with_oss(&mut self, oss: Oss) -> Result<Measurement, BMPError<I2C::Error>> {
+        let previous_oss = self.oss;
+        self.oss = oss;
+        let result = self.measure().await;
+        self.oss = previous_oss;
+        result
+    }
+
+    async fn check_soft_reset(&mut self) -> Result<(), BMPError<I2C::Error>> {
+        self.soft_reset().await?;
+        self.delayer.delay_us(BMP_STARTUP_TIME_US).await;
+
+        let mut id = [0];
+        self.read_register(Operation::Reset, BMP_ID_REG, &mut id)
+            .await?;
+        match id[0] {
+            BMP_DEVICE_ID => Ok(()),
+            id => Err(BMPError::InvalidDeviceId(id)),
+        }
+    }
+
+    /// Measure the uncompensated temperature (UT) on the BMP device.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// Raw 16-bit temperature reading
+    pub async fn read_raw_temperature(&mut self) -> Result<u16, BMPError<I2C::Error>> {
+        let result = self.read_uncompensated_temperature().await;
+        self.record_health(&result);
+        result
+    }
+
+    /// Measure the uncompensated temperature (UT) and pressure (UP) on the BMP device.
+    /// The resulting sample can be compensated with [`compensate`](crate::compensate), using this driver's [`calibration data`](BMP::calibration_data).
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// [`RawSample`](RawSample) with both readings and the oversampling setting used
+    pub async fn read_raw_pressure(&mut self) -> Result<RawSample, BMPError<I2C::Error>> {
+        let result = self.read_sample().await;
+        self.record_health(&result);
+        result
+    }
+
     /// Measure and calculate temperature from the BMP device.
     ///
     /// ### Arguments
@@ -197,10 +533,9 @@ where
     ///
     /// `temperature` in degrees Celsius (ºC)
     pub async fn read_temperature(&mut self) -> Result<f32, BMPError<I2C::Error>> {
-        let ut = self.read_uncompensated_temperature().await?;
-        let (temperature, _) = logic::calculate_temperature(&self.calib_data, ut);
-
-        Ok(temperature)
+        let result = self.measure_temperature().await;
+        self.record_health(&result);
+        result
     }
 
     /// Measure and calculate pressure from the BMP device.
@@ -213,14 +548,24 @@ where
     ///
     /// `pressure` in pascals (Pa)
     pub async fn read_pressure(&mut self) -> Result<i32, BMPError<I2C::Error>> {
-        let ut = self.read_uncompensated_temperature().await?;
-        let (_, b5) = logic::calculate_temperature(&self.calib_data, ut);
-        let up = self.read_uncompensated_pressure().await?;
+        let result = self.measure_pressure().await;
+        self.record_health(&result);
+        result
+    }
 
-        match logic::calculate_pressure(&self.calib_data, self.oss.val(), b5, up) {
-            Some(pressure) => Ok(pressure),
-            None => Err(BMPError::InvalidCalibrationData),
-        }
+    /// Measure and calculate both temperature and pressure from the BMP device.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// [`Measurement`](Measurement) with `temperature` in degrees Celsius (ºC) and `pressure` in pascals (Pa)
+    pub async fn read_measurement(&mut self) -> Result<Measurement, BMPError<I2C::Error>> {
+        let result = self.measure().await;
+        self.record_health(&result);
+        result
     }
 
     /// Calculate altitude from pressure pressure measurement on the BMP device.
@@ -238,7 +583,8 @@ where
         Ok(logic::calculate_altitude(pressure, self.sea_level_pressure))
     }
 
-    /// Trigger a soft reset of the BMP device. It will perform the same sequence as power on reset.
+    /// Calculate the pressure altitude from a pressure measurement on the BMP device, i.e. the altitude
+    /// relative to the standard pressure of 1013.25 hPa, regardless of the pressure at sea level.
     ///
     /// ### Arguments
     ///
@@ -246,12 +592,149 @@ where
     ///
     /// ### Returns
     ///
+    /// `pressure altitude` in meters (m)
+    pub async fn read_pressure_altitude(&mut self) -> Result<f32, BMPError<I2C::Error>> {
+        let pressure = self.read_pressure().await?;
+        Ok(logic::calculate_pressure_altitude(pressure))
+    }
+
+    /// Calculate the density altitude from temperature and pressure measurements on the BMP device,
+    /// i.e. the altitude in the standard atmosphere with the same density of dry air.
+    ///
+    /// ### Arguments
+    ///
     /// None
-    pub async fn soft_reset(&mut self) -> Result<(), BMPError<I2C::Error>> {
-        self.i2c
-            .write(self.address, &[BMP_SOFT_RST_REG, 0xB6])
-            .await?;
-        Ok(())
+    ///
+    /// ### Returns
+    ///
+    /// `density altitude` in meters (m)
+    pub async fn read_density_altitude(&mut self) -> Result<f32, BMPError<I2C::Error>> {
+        let measurement = self.read_measurement().await?;
+        Ok(logic::calculate_density_altitude(
+            measurement.pressure,
+            measurement.temperature,
+        ))
+    }
+
+    /// Calculate the density of dry air from temperature and pressure measurements on the BMP device.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// `air density` in kilograms per cubic meter (kg/m³)
+    pub async fn read_air_density(&mut self) -> Result<f32, BMPError<I2C::Error>> {
+        let measurement = self.read_measurement().await?;
+        Ok(logic::calculate_air_density(
+            measurement.pressure,
+            measurement.temperature,
+        ))
+    }
+
+    /// Measure temperature and pressure into `log`, if its next record is due.
+    ///
+    /// ### Arguments
+    ///
+    /// * `log` - [`MeasurementLog`] to add the measurement to.
+    /// * `timestamp_ms` - Current time in milliseconds (ms), from the clock used for `log`.
+    ///
+    /// ### Returns
+    ///
+    /// `true` if a measurement was taken and logged, `false` if no record was due
+    pub async fn log_measurement<const N: usize>(
+        &mut self,
+        log: &mut MeasurementLog<N>,
+        timestamp_ms: u32,
+    ) -> Result<bool, BMPError<I2C::Error>> {
+        if !log.is_due(timestamp_ms) {
+            return Ok(false);
+        }
+        let measurement = self.read_measurement().await?;
+        log.push(timestamp_ms, &measurement);
+        Ok(true)
+    }
+
+    /// Get the calibration data read from the device during initialization.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// The device's [`CalibrationData`](CalibrationData)
+    pub fn calibration_data(&self) -> CalibrationData {
+        self.calib_data
+    }
+
+    /// Run a self-test of the device: checks the device identifier and calibration data, takes a
+    /// measurement with every oversampling setting, checks they are plausible and consistent with each
+    /// other, and finally checks that the device recovers from a soft reset.
+    /// Every check is run even if previous ones failed. Its readings don't count towards stuck-sensor detection.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// [`SelfTestReport`](SelfTestReport) with the result of each check, see [`passed`](SelfTestReport::passed)
+    pub async fn self_test(&mut self) -> SelfTestReport<I2C::Error> {
+        let connection = self.test_connection().await;
+        let calibration = self.calib_data.is_valid();
+        // Readings taken back to back legitimately repeat, keep them out of stuck-sensor detection
+        let (stuck_threshold, ut_run, up_run) =
+            (self.stuck_threshold.take(), self.ut_run, self.up_run);
+        let measurements = [
+            self.measure_with_oss(Oss::LowPower).await,
+            self.measure_with_oss(Oss::Standard).await,
+            self.measure_with_oss(Oss::HighRes).await,
+            self.measure_with_oss(Oss::UltraHighRes).await,
+        ];
+        (self.stuck_threshold, self.ut_run, self.up_run) = (stuck_threshold, ut_run, up_run);
+        let consistent = logic::measurements_are_consistent(
+            measurements.iter().filter_map(|m| m.as_ref().ok()),
+            SELF_TEST_MAX_TEMPERATURE_SPREAD,
+            SELF_TEST_MAX_PRESSURE_SPREAD,
+        );
+        let soft_reset = self.check_soft_reset().await;
+
+        SelfTestReport {
+            connection,
+            calibration,
+            measurements,
+            consistent,
+            soft_reset,
+        }
+    }
+
+    /// Get a summary of the device's health, based on the measurements taken so far.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// The device's [`Health`](Health)
+    pub fn health(&self) -> Health {
+        self.health
+    }
+
+    /// Set the range of plausible readings, outside of which measurements fail with [`OutOfRange`](BMPError::OutOfRange).
+    /// See [PlausibilityLimits](PlausibilityLimits).
+    ///
+    /// ### Arguments
+    ///
+    /// * `limits` - Driver's new [PlausibilityLimits](PlausibilityLimits)
+    ///
+    /// ### Returns
+    ///
+    /// Nothing
+    pub fn set_plausibility_limits(&mut self, limits: PlausibilityLimits) {
+        self.limits = limits;
     }
 
     /// Set the oversampling setting for the driver's measurements.
@@ -283,4 +766,18 @@ where
         assert!(sea_level_pressure > 0);
         self.sea_level_pressure = sea_level_pressure;
     }
+
+    /// Set the clock timestamping successful measurements in the driver's [`health`](BMP::health).
+    ///
+    /// ### Arguments
+    ///
+    /// * `clock` - Monotonic clock in milliseconds (ms), e.g. `|| Instant::now().as_millis() as u32`
+    ///   with `embassy-time`.
+    ///
+    /// ### Returns
+    ///
+    /// Nothing
+    pub fn set_clock(&mut self, clock: fn() -> u32) {
+        self.clock = Some(clock);
+    }
 }
@@ -0,0 +1,182 @@
+//! Formatters turning measurements into text for host-side tools, enabled with the `std` feature.
+//!
+//! Every formatter tags measurements with the address of the sensor they come from, and with an
+//! optional Unix timestamp in milliseconds:
+//!
+//! ```ignore
+//! let measurement = bmp.read_measurement()?;
+//! let now_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+//! println!("{}", line_protocol("barometer", 0x77, &measurement, Some(now_ms)));
+//! // barometer,address=0x77 temperature_c=15,pressure_pa=69964i 1700000000000000000
+//! ```
+
+use std::fmt::Write;
+use std::string::String;
+
+use crate::types::Measurement;
+
+/// Header of the rows written by [`csv_row`].
+pub const CSV_HEADER: &str = "timestamp_ms,address,temperature_c,pressure_pa";
+
+/// Format a measurement as an InfluxDB line protocol point.
+///
+/// ### Arguments
+///
+/// * `name` - Name of the InfluxDB measurement.
+/// * `address` - I2C address of the sensor, added as the `address` tag.
+/// * `measurement` - The [`Measurement`], written as the `temperature_c` and `pressure_pa` fields. A
+///   temperature which isn't finite is left out, as InfluxDB would reject the whole point.
+/// * `timestamp_ms` - Unix timestamp in milliseconds (ms), or `None` to let the server timestamp the point.
+///
+/// ### Returns
+///
+/// The point, without a trailing newline
+pub fn line_protocol(
+    name: &str,
+    address: u8,
+    measurement: &Measurement,
+    timestamp_ms: Option<u64>,
+) -> String {
+    let mut line = String::new();
+    for c in name.chars() {
+        if matches!(c, ',' | ' ' | '\\') {
+            line.push('\\');
+        }
+        line.push(c);
+    }
+    let _ = write!(line, ",address=0x{address:02X} ");
+    if measurement.temperature.is_finite() {
+        let _ = write!(line, "temperature_c={},", measurement.temperature);
+    }
+    let _ = write!(line, "pressure_pa={}i", measurement.pressure);
+    if let Some(timestamp_ms) = timestamp_ms {
+        let _ = write!(line, " {}", timestamp_ms as u128 * 1_000_000);
+    }
+    line
+}
+
+/// Format a measurement as a CSV row, with the columns of [`CSV_HEADER`].
+///
+/// ### Arguments
+///
+/// * `address` - I2C address of the sensor.
+/// * `measurement` - The [`Measurement`], with temperature in ºC and pressure in Pa. A temperature which
+///   isn't finite is left empty.
+/// * `timestamp_ms` - Unix timestamp in milliseconds (ms), left empty if `None`.
+///
+/// ### Returns
+///
+/// The row, without a trailing newline
+pub fn csv_row(address: u8, measurement: &Measurement, timestamp_ms: Option<u64>) -> String {
+    let mut row = String::new();
+    if let Some(timestamp_ms) = timestamp_ms {
+        let _ = write!(row, "{timestamp_ms}");
+    }
+    let _ = write!(row, ",0x{address:02X},");
+    if measurement.temperature.is_finite() {
+        let _ = write!(row, "{}", measurement.temperature);
+    }
+    let _ = write!(row, ",{}", measurement.pressure);
+    row
+}
+
+/// Format a measurement as a JSON object, with the unit of each value.
+///
+/// ### Arguments
+///
+/// * `address` - I2C address of the sensor.
+/// * `measurement` - The [`Measurement`]. A temperature which isn't finite is written as `null`.
+/// * `timestamp_ms` - Unix timestamp in milliseconds (ms), written as `null` if `None`.
+///
+/// ### Returns
+///
+/// The object, on a single line
+pub fn json(address: u8, measurement: &Measurement, timestamp_ms: Option<u64>) -> String {
+    let mut object = String::new();
+    let _ = write!(
+        object,
+        "{{\"address\":\"0x{address:02X}\",\"timestamp_ms\":"
+    );
+    match timestamp_ms {
+        Some(timestamp_ms) => {
+            let _ = write!(object, "{timestamp_ms}");
+        }
+        None => object.push_str("null"),
+    }
+    object.push_str(",\"temperature\":{\"value\":");
+    if measurement.temperature.is_finite() {
+        let _ = write!(object, "{}", measurement.temperature);
+    } else {
+        object.push_str("null");
+    }
+    let _ = write!(
+        object,
+        ",\"unit\":\"°C\"}},\"pressure\":{{\"value\":{},\"unit\":\"Pa\"}}}}",
+        measurement.pressure
+    );
+    object
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEASUREMENT: Measurement = Measurement {
+        temperature: 15.3,
+        pressure: 69_964,
+    };
+
+    /// Measurement with a temperature which isn't finite.
+    const INVALID: Measurement = Measurement {
+        temperature: f32::NAN,
+        pressure: 0,
+    };
+
+    #[test]
+    fn formats_line_protocol() {
+        assert_eq!(
+            line_protocol("barometer", 0x77, &MEASUREMENT, Some(1_700_000_000_000)),
+            "barometer,address=0x77 temperature_c=15.3,pressure_pa=69964i 1700000000000000000"
+        );
+        assert_eq!(
+            line_protocol("indoor air, hall", 0x77, &MEASUREMENT, None),
+            "indoor\\ air\\,\\ hall,address=0x77 temperature_c=15.3,pressure_pa=69964i"
+        );
+        assert_eq!(
+            line_protocol("barometer", 0x77, &INVALID, None),
+            "barometer,address=0x77 pressure_pa=0i"
+        );
+    }
+
+    #[test]
+    fn formats_csv_rows() {
+        assert_eq!(
+            csv_row(0x77, &MEASUREMENT, Some(1_700_000_000_000)),
+            "1700000000000,0x77,15.3,69964"
+        );
+        assert_eq!(csv_row(0x77, &MEASUREMENT, None), ",0x77,15.3,69964");
+        assert_eq!(csv_row(0x77, &INVALID, None), ",0x77,,0");
+        let infinite = Measurement {
+            temperature: f32::INFINITY,
+            ..INVALID
+        };
+        assert_eq!(csv_row(0x77, &infinite, None), ",0x77,,0");
+        assert_eq!(CSV_HEADER.split(',').count(), 4);
+    }
+
+    #[test]
+    fn formats_json() {
+        assert_eq!(
+            json(0x77, &MEASUREMENT, Some(1_700_000_000_000)),
+            "{\"address\":\"0x77\",\"timestamp_ms\":1700000000000,\
+             \"temperature\":{\"value\":15.3,\"unit\":\"°C\"},\
+             \"pressure\":{\"value\":69964,\"unit\":\"Pa\"}}"
+        );
+        assert_eq!(
+            json(0x77, &INVALID, None),
+            "{\"address\":\"0x77\",\"timestamp_ms\":null,\
+             \"temperature\":{\"value\":null,\"unit\":\"°C\"},\
+             \"pressure\":{\"value\":0,\"unit\":\"Pa\"}}"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Filters smoothing noisy altitude or pressure readings.
+//!
+//! Even at [`UltraHighRes`](crate::Oss::UltraHighRes), altitudes calculated from single pressure readings
+//! are noisy by about ±0.5 m. [`AltitudeKalman`] tracks altitude and vertical speed, while [`Ema`] is a
+//! simpler exponential moving average of any value:
+//!
+//! ```ignore
+//! let mut kalman = AltitudeKalman::new(KalmanConfig::DEFAULT);
+//! loop {
+//!     let pressure = bmp.read_pressure()?;
+//!     let estimate = kalman.update_pressure(pressure, sea_level_pressure, 0.1);
+//!     // estimate.altitude, estimate.vertical_speed
+//! }
+//! ```
+
+use crate::logic;
+
+/// Exponential moving average, `value = alpha * sample + (1 - alpha) * value`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ema {
+    alpha: f32,
+    value: Option<f32>,
+}
+
+impl Ema {
+    /// Creates a new moving average.
+    ///
+    /// ### Arguments
+    ///
+    /// * `alpha` - Smoothing factor, from `0` (exclusive, samples are ignored) to `1` (no smoothing).
+    pub fn new(alpha: f32) -> Self {
+        assert!(alpha > 0.0 && alpha <= 1.0);
+        Ema { alpha, value: None }
+    }
+
+    /// Adds a sample to the average.
+    ///
+    /// ### Arguments
+    ///
+    /// * `sample` - New sample, the first one initializes the average.
+    ///
+    /// ### Returns
+    ///
+    /// The updated average
+    pub fn update(&mut self, sample: f32) -> f32 {
+        let value = match self.value {
+            Some(value) => value + self.alpha * (sample - value),
+            None => sample,
+        };
+        self.value = Some(value);
+        value
+    }
+
+    /// Get the current average, if any sample was added.
+    pub fn value(&self) -> Option<f32> {
+        self.value
+    }
+
+    /// Forget every sample added so far.
+    pub fn reset(&mut self) {
+        self.value = None;
+    }
+}
+
+/// Noise model of an [`AltitudeKalman`] filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KalmanConfig {
+    /// Variance of the vertical acceleration, in m²/s⁴. Higher values follow altitude changes faster.
+    pub process_noise: f32,
+    /// Variance of the altitude readings, in m². Higher values smooth readings more.
+    pub measurement_noise: f32,
+}
+
+impl KalmanConfig {
+    /// Suited for readings with ±0.5 m of noise, e.g. from a variometer.
+    pub const DEFAULT: KalmanConfig = KalmanConfig {
+        process_noise: 1.0,
+        measurement_noise: 0.25,
+    };
+}
+
+impl Default for KalmanConfig {
+    fn default() -> Self {
+        KalmanConfig::DEFAULT
+    }
+}
+
+/// Filtered altitude and vertical speed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AltitudeEstimate {
+    /// Altitude in meters (m).
+    pub altitude: f32,
+    /// Vertical speed in meters per second (m/s), positive when climbing.
+    pub vertical_speed: f32,
+}
+
+/// Uncertainty of the initial vertical speed, in m²/s², before any change in altitude was seen.
+const INITIAL_SPEED_VARIANCE: f32 = 10.0;
+
+/// Kalman filter estimating altitude and vertical speed from altitude readings, assuming a constant
+/// vertical speed disturbed by random accelerations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AltitudeKalman {
+    config: KalmanConfig,
+    estimate: Option<AltitudeEstimate>,
+    /// Covariance of the altitude and vertical speed errors.
+    covariance: [[f32; 2]; 2],
+}
+
+impl AltitudeKalman {
+    /// Creates a new filter.
+    ///
+    /// ### Arguments
+    ///
+    /// * `config` - [`Noise model`](KalmanConfig) of the readings.
+    pub fn new(config: KalmanConfig) -> Self {
+        AltitudeKalman {
+            config,
+            estimate: None,
+            covariance: [[0.0; 2]; 2],
+        }
+    }
+
+    /// Adds an altitude reading to the filter.
+    ///
+    /// ### Arguments
+    ///
+    /// * `altitude` - Altitude in meters (m), e.g. from [`BMP::read_altitude`](crate::BMP::read_altitude).
+    /// * `dt` - Time since the previous reading, in seconds (s). Ignored for the first reading.
+    ///
+    /// ### Returns
+    ///
+    /// The updated [`AltitudeEstimate`]
+    pub fn update(&mut self, altitude: f32, dt: f32) -> AltitudeEstimate {
+        let q = self.config.process_noise;
+        let r = self.config.measurement_noise;
+        let Some(AltitudeEstimate {
+            altitude: predicted,
+            vertical_speed,
+        }) = self.estimate
+        else {
+            self.covariance = [[r, 0.0], [0.0, INITIAL_SPEED_VARIANCE]];
+            let estimate = AltitudeEstimate {
+                altitude,
+                vertical_speed: 0.0,
+            };
+            self.estimate = Some(estimate);
+            return estimate;
+        };
+
+        // Predict, moving at constant speed for dt
+        let predicted = predicted + vertical_speed * dt;
+        let [[p00, p01], [p10, p11]] = self.covariance;
+        let (dt2, dt3, dt4) = (dt * dt, dt * dt * dt, dt * dt * dt * dt);
+        let p00 = p00 + dt * (p01 + p10) + dt2 * p11 + q * dt4 / 4.0;
+        let p01 = p01 + dt * p11 + q * dt3 / 2.0;
+        let p10 = p10 + dt * p11 + q * dt3 / 2.0;
+        let p11 = p11 + q * dt2;
+
+        // Correct with the reading
+        let innovation = altitude - predicted;
+        let s = p00 + r;
+        let (k0, k1) = (p00 / s, p10 / s);
+        self.covariance = [
+            [(1.0 - k0) * p00, (1.0 - k0) * p01],
+            [p10 - k1 * p00, p11 - k1 * p01],
+        ];
+        let estimate = AltitudeEstimate {
+            altitude: predicted + k0 * innovation,
+            vertical_speed: vertical_speed + k1 * innovation,
+        };
+        self.estimate = Some(estimate);
+        estimate
+    }
+
+    /// Adds a pressure reading to the filter, converted to altitude as by [`BMP::read_altitude`](crate::BMP::read_altitude).
+    ///
+    /// ### Arguments
+    ///
+    /// * `pressure` - Pressure in pascals (Pa), e.g. from [`BMP::read_pressure`](crate::BMP::read_pressure).
+    /// * `sea_level_pressure` - Pressure at sea level in pascals (Pa).
+    /// * `dt` - Time since the previous reading, in seconds (s). Ignored for the first reading.
+    ///
+    /// ### Returns
+    ///
+    /// The updated [`AltitudeEstimate`]
+    pub fn update_pressure(
+        &mut self,
+        pressure: i32,
+        sea_level_pressure: i32,
+        dt: f32,
+    ) -> AltitudeEstimate {
+        self.update(logic::calculate_altitude(pressure, sea_level_pressure), dt)
+    }
+
+    /// Get the current estimate, if any reading was added.
+    pub fn estimate(&self) -> Option<AltitudeEstimate> {
+        self.estimate
+    }
+
+    /// Forget every reading added so far.
+    pub fn reset(&mut self) {
+        self.estimate = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ema_smooths_samples() {
+        let mut ema = Ema::new(0.5);
+
+        assert_eq!(ema.value(), None);
+        assert_eq!(ema.update(10.0), 10.0);
+        assert_eq!(ema.update(20.0), 15.0);
+        assert_eq!(ema.update(20.0), 17.5);
+        ema.reset();
+        assert_eq!(ema.update(4.0), 4.0);
+    }
+
+    #[test]
+    fn kalman_starts_at_first_reading() {
+        let mut kalman = AltitudeKalman::new(KalmanConfig::DEFAULT);
+
+        assert_eq!(kalman.estimate(), None);
+        assert_eq!(
+            kalman.update(250.0, 0.1),
+            AltitudeEstimate {
+                altitude: 250.0,
+                vertical_speed: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn kalman_converts_pressure_to_altitude() {
+        let mut kalman = AltitudeKalman::new(KalmanConfig::DEFAULT);
+        let estimate = kalman.update_pressure(93_810, 101_325, 0.1);
+
+        assert!((estimate.altitude - 645.0).abs() < 0.5);
+        kalman.reset();
+        assert_eq!(kalman.estimate(), None);
+    }
+}
@@ -10,8 +10,20 @@
 //! bmp085-180-rs = { version = "1.0.0", features = [ "async" ] }
 //! ```
 //!
+//! The `mux` feature enables the `mux` module, to use devices behind a TCA9548A/PCA9548 I2C multiplexer.
+//! The `embassy` feature, which implies `async`, enables the `sampler` module with a ready-made sampling task.
+//! The `stream` feature, which implies `async`, adds `BMP::stream` for periodic measurements as a `futures` stream.
+//! The `std` feature enables the `export` module, formatting measurements as InfluxDB line protocol, CSV or JSON.
+//!
 //! ### Usage
 //!
+//! [`BMP::new`](BMP::new) creates an uninitialized driver, which must be turned into a calibrated one
+//! with [`BMP::init`](BMP::init) before taking measurements:
+//! ```ignore
+//! let mut bmp = BMP::new(i2c, delay, Default::default()).init()?;
+//! let pressure = bmp.read_pressure()?;
+//! ```
+//!
 //! See the following driver methods:
 //!
 //! #### [`BMP::read_temperature`](BMP::read_temperature)
@@ -19,15 +31,70 @@
 //! #### [`BMP::read_pressure`](BMP::read_pressure)
 //!
 //! #### [`BMP::read_altitude`](BMP::read_altitude)
+//!
+//! #### [`BMP::read_density_altitude`](BMP::read_density_altitude)
+//!
+//! #### [`BMP::read_air_density`](BMP::read_air_density)
+//!
+//! #### [`BMP::self_test`](BMP::self_test)
+//!
+//! Since the device address is fixed, several devices share a bus through a TCA9548A/PCA9548 multiplexer.
+//! The [`scan`] module finds devices on the multiplexer's channels, and [`BmpArray`] takes synchronized
+//! measurements with the drivers of all of them.
+//!
+//! Uncompensated readings can also be taken with [`BMP::read_raw_pressure`](BMP::read_raw_pressure)
+//! and compensated later on, even on another machine, with the [`compensation`] module. The [`logic`] module holds every compensation and
+//! conversion formula used by the driver, e.g. [`logic::pressure_at_altitude`] to turn altitude thresholds
+//! into pressure thresholds.
+//!
+//! Readings are compensated with the datasheet's integer algorithm by default. Setting
+//! [`Config::compensation`] to [`CompensationAlgorithm::FloatingPoint`] selects a floating-point polynomial
+//! algorithm instead, with smoother output, see [`logic::compensate_float`].
+//!
+//! The [`filter`] module smooths noisy altitude readings, and estimates vertical speed.
+//! The [`vario`] module calculates the vertical speed over a window of readings, for variometers.
+//! The [`weather`] module derives pressure tendencies and forecasts from a history of readings.
+//! The [`logger`] module keeps the last measurements in RAM, see [`BMP::log_measurement`].
+//! The [`codec`] module encodes measurements into compact binary records, or CayenneLPP payloads.
+//!
+//! Compensated readings outside of the datasheet operating range are rejected as implausible, see
+//! [`PlausibilityLimits`] to adjust or disable the limits.
 
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
 #[cfg(all(feature = "async", feature = "sync"))]
 compile_error!("Both `sync` and `async` features cannot be enabled.");
 
+mod array;
+pub mod codec;
+pub mod compensation;
 mod constants;
 mod driver;
-mod logic;
+#[cfg(feature = "std")]
+pub mod export;
+pub mod filter;
+pub mod logger;
+pub mod logic;
+#[cfg(feature = "mux")]
+pub mod mux;
+#[cfg(feature = "embassy")]
+pub mod sampler;
+pub mod scan;
+#[cfg(test)]
+mod simulator;
+#[cfg(feature = "stream")]
+mod stream;
 mod types;
+pub mod vario;
+pub mod weather;
 
-pub use types::{BMPError, Config, Oss, BMP};
+pub use array::BmpArray;
+pub use logic::compensate;
+pub use types::{
+    BMPError, CalibrationData, CompensationAlgorithm, CompensationError, Config, ConversionTiming,
+    Health, Initialized, Measurement, Operation, Oss, PlausibilityLimits, RawSample, RetryPolicy,
+    SelfTestReport, TransactionStats, Uninitialized, BMP,
+};
@@ -0,0 +1,314 @@
+//! Fixed-capacity log of measurements, kept in RAM on boards without external storage.
+//!
+//! Each measurement is stored in 4 bytes: the temperature in 0.1 ºC, and the pressure as a difference to
+//! the previous record, so that a [`MeasurementLog`] of 1440 records holds 24 hours of measurements
+//! taken every minute in less than 6 KiB:
+//!
+//! ```ignore
+//! let mut log = MeasurementLog::<1440>::new(60_000);
+//! loop {
+//!     bmp.log_measurement(&mut log, now_ms())?;
+//!     if let Some(statistics) = log.statistics() {
+//!         // statistics.min, statistics.max, statistics.mean
+//!     }
+//! }
+//! ```
+
+use crate::types::Measurement;
+
+/// Compact record of a [`Measurement`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Record {
+    /// Pressure in Pa, relative to the previous record.
+    pressure_delta: i16,
+    /// Temperature in 0.1 ºC.
+    temperature: i16,
+}
+
+/// Lowest, highest and mean values of each quantity, over every record of a [`MeasurementLog`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Statistics {
+    /// Lowest temperature and lowest pressure, which may come from different measurements.
+    pub min: Measurement,
+    /// Highest temperature and highest pressure, which may come from different measurements.
+    pub max: Measurement,
+    /// Mean temperature and pressure.
+    pub mean: Measurement,
+}
+
+/// Log of the last `N` measurements, taken at a fixed interval.
+///
+/// Temperatures are rounded to 0.1 ºC. Pressure differences between consecutive records are limited to
+/// ±32767 Pa, larger ones are saturated and caught up with over the following records.
+#[derive(Debug, Clone)]
+pub struct MeasurementLog<const N: usize> {
+    interval_ms: u32,
+    records: [Record; N],
+    /// Index of the oldest record.
+    start: usize,
+    len: usize,
+    /// Pressure of the oldest record, in Pa.
+    first_pressure: i32,
+    /// Pressure of the newest record, in Pa.
+    last_pressure: i32,
+    /// Time the newest record was due at, in ms.
+    last_timestamp_ms: Option<u32>,
+}
+
+impl<const N: usize> MeasurementLog<N> {
+    /// Creates a new, empty log.
+    ///
+    /// ### Arguments
+    ///
+    /// * `interval_ms` - Time between records in milliseconds (ms).
+    pub fn new(interval_ms: u32) -> Self {
+        assert!(N > 0);
+        MeasurementLog {
+            interval_ms,
+            records: [Record::default(); N],
+            start: 0,
+            len: 0,
+            first_pressure: 0,
+            last_pressure: 0,
+            last_timestamp_ms: None,
+        }
+    }
+
+    /// Check whether the next record is due.
+    ///
+    /// ### Arguments
+    ///
+    /// * `timestamp_ms` - Current time in milliseconds (ms), from any monotonic clock.
+    ///   Wrapping around is supported.
+    ///
+    /// ### Returns
+    ///
+    /// `true` if the log is empty, or the interval elapsed since the previous record was due
+    pub fn is_due(&self, timestamp_ms: u32) -> bool {
+        self.last_timestamp_ms
+            .is_none_or(|last| timestamp_ms.wrapping_sub(last) >= self.interval_ms)
+    }
+
+    /// Adds a measurement to the log, dropping the oldest record once full.
+    ///
+    /// ### Arguments
+    ///
+    /// * `timestamp_ms` - Time of the measurement in milliseconds (ms), from any monotonic clock.
+    /// * `measurement` - The [`Measurement`] to add, regardless of whether it is due.
+    pub fn push(&mut self, timestamp_ms: u32, measurement: &Measurement) {
+        let temperature = libm::roundf(measurement.temperature * 10.0) as i16;
+        let record = if self.len == 0 {
+            self.first_pressure = measurement.pressure;
+            self.last_pressure = measurement.pressure;
+            Record {
+                pressure_delta: 0,
+                temperature,
+            }
+        } else {
+            let delta =
+                (measurement.pressure - self.last_pressure).clamp(i16::MIN as i32, i16::MAX as i32);
+            self.last_pressure += delta;
+            Record {
+                pressure_delta: delta as i16,
+                temperature,
+            }
+        };
+
+        if self.len == N {
+            self.records[self.start] = record;
+            self.start = (self.start + 1) % N;
+            self.first_pressure += self.records[self.start].pressure_delta as i32;
+        } else {
+            self.records[(self.start + self.len) % N] = record;
+            self.len += 1;
+        }
+
+        // Keep the schedule when logging early, stay on it when logging a bit late, but not when
+        // records were missed
+        self.last_timestamp_ms = Some(match self.last_timestamp_ms {
+            Some(last) if timestamp_ms.wrapping_sub(last) < self.interval_ms => last,
+            Some(last) if timestamp_ms.wrapping_sub(last) < self.interval_ms.saturating_mul(2) => {
+                last.wrapping_add(self.interval_ms)
+            }
+            _ => timestamp_ms,
+        });
+    }
+
+    /// Iterate over the logged measurements, from the oldest to the newest one.
+    pub fn iter(&self) -> impl Iterator<Item = Measurement> + '_ {
+        let mut pressure = self.first_pressure;
+        (0..self.len).map(move |i| {
+            let record = self.records[(self.start + i) % N];
+            if i > 0 {
+                pressure += record.pressure_delta as i32;
+            }
+            Measurement {
+                temperature: record.temperature as f32 / 10.0,
+                pressure,
+            }
+        })
+    }
+
+    /// Calculate statistics over every logged measurement.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// [`Statistics`] of the logged measurements, `None` if the log is empty
+    pub fn statistics(&self) -> Option<Statistics> {
+        let first = self.iter().next()?;
+        let (mut min, mut max) = (first, first);
+        let (mut temperature_sum, mut pressure_sum) = (0.0, 0i64);
+        for measurement in self.iter() {
+            min.temperature = min.temperature.min(measurement.temperature);
+            max.temperature = max.temperature.max(measurement.temperature);
+            min.pressure = min.pressure.min(measurement.pressure);
+            max.pressure = max.pressure.max(measurement.pressure);
+            temperature_sum += measurement.temperature;
+            pressure_sum += measurement.pressure as i64;
+        }
+
+        Some(Statistics {
+            min,
+            max,
+            mean: Measurement {
+                temperature: temperature_sum / self.len as f32,
+                pressure: (pressure_sum / self.len as i64) as i32,
+            },
+        })
+    }
+
+    /// Get the number of logged measurements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether no measurement was logged.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the largest number of measurements the log holds.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Forget every logged measurement.
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+        self.last_timestamp_ms = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(temperature: f32, pressure: i32) -> Measurement {
+        Measurement {
+            temperature,
+            pressure,
+        }
+    }
+
+    #[test]
+    fn records_take_four_bytes() {
+        assert_eq!(core::mem::size_of::<Record>(), 4);
+    }
+
+    #[test]
+    fn iterates_over_logged_measurements() {
+        let mut log = MeasurementLog::<4>::new(1000);
+        log.push(0, &measurement(15.0, 69_964));
+        log.push(1000, &measurement(15.3, 69_950));
+        log.push(2000, &measurement(-4.2, 70_100));
+
+        let mut measurements = log.iter();
+        assert_eq!(measurements.next(), Some(measurement(15.0, 69_964)));
+        assert_eq!(measurements.next(), Some(measurement(15.3, 69_950)));
+        assert_eq!(measurements.next(), Some(measurement(-4.2, 70_100)));
+        assert_eq!(measurements.next(), None);
+        assert_eq!(log.len(), 3);
+    }
+
+    #[test]
+    fn drops_oldest_measurements_once_full() {
+        let mut log = MeasurementLog::<3>::new(1000);
+        for i in 0..5 {
+            log.push(i * 1000, &measurement(20.0, 100_000 + 10 * i as i32));
+        }
+
+        let mut pressures = log.iter().map(|measurement| measurement.pressure);
+        assert_eq!(pressures.next(), Some(100_020));
+        assert_eq!(pressures.next(), Some(100_030));
+        assert_eq!(pressures.next(), Some(100_040));
+        assert_eq!(pressures.next(), None);
+        assert_eq!(log.capacity(), 3);
+    }
+
+    #[test]
+    fn saturates_large_pressure_steps() {
+        let mut log = MeasurementLog::<4>::new(1000);
+        log.push(0, &measurement(20.0, 30_000));
+        log.push(1000, &measurement(20.0, 100_000));
+        log.push(2000, &measurement(20.0, 100_000));
+        log.push(3000, &measurement(20.0, 100_000));
+
+        let mut pressures = log.iter().map(|measurement| measurement.pressure);
+        assert_eq!(pressures.next(), Some(30_000));
+        assert_eq!(pressures.next(), Some(62_767));
+        assert_eq!(pressures.next(), Some(95_534));
+        assert_eq!(pressures.next(), Some(100_000));
+    }
+
+    #[test]
+    fn calculates_statistics() {
+        let mut log = MeasurementLog::<8>::new(1000);
+        assert_eq!(log.statistics(), None);
+        log.push(0, &measurement(10.0, 100_000));
+        log.push(1000, &measurement(20.0, 99_000));
+        log.push(2000, &measurement(15.0, 101_000));
+
+        assert_eq!(
+            log.statistics(),
+            Some(Statistics {
+                min: measurement(10.0, 99_000),
+                max: measurement(20.0, 101_000),
+                mean: measurement(15.0, 100_000),
+            })
+        );
+        log.clear();
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn records_are_due_at_interval() {
+        let mut log = MeasurementLog::<8>::new(1000);
+        assert!(log.is_due(500));
+        log.push(500, &measurement(15.0, 100_000));
+        assert!(!log.is_due(1499));
+        assert!(log.is_due(1500));
+        // Logging late doesn't delay the following records
+        log.push(1700, &measurement(15.0, 100_000));
+        assert!(log.is_due(2500));
+        // After missing records, the schedule restarts
+        log.push(9000, &measurement(15.0, 100_000));
+        assert!(!log.is_due(9999));
+        assert!(log.is_due(10_000));
+    }
+
+    #[test]
+    fn early_records_keep_schedule() {
+        let mut log = MeasurementLog::<8>::new(1000);
+        log.push(500, &measurement(15.0, 100_000));
+        log.push(800, &measurement(15.0, 100_000));
+        assert_eq!(log.len(), 2);
+        assert!(!log.is_due(900));
+        assert!(!log.is_due(1499));
+        assert!(log.is_due(1500));
+    }
+}
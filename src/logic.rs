@@ -1,62 +1,387 @@
-use crate::types::CalibrationData;
+//! Compensation and conversion formulas used by the driver, for use on readings taken elsewhere.
+
+use crate::constants::*;
+use crate::types::{
+    CalibrationData, CompensationAlgorithm, CompensationError, Measurement, Oss, RawSample,
+};
+
+/// Largest possible uncompensated temperature reading (16 bits).
+const UT_MAX: i32 = 0xFFFF;
+
+/// Bound on the magnitude of the `b5` coefficient, given |x1| < 2^17 and |x2| <= 2^26.
+const B5_LIMIT: i32 = (1 << 26) + (1 << 17);
 
 /// Calculates temperature from uncompensated temperature value
 ///
+/// All intermediate values are computed in 64 bits, which can't overflow for any 16-bit
+/// calibration coefficients and readings.
+///
+/// ### Arguments
+///
+/// * `calib_data` - Calibration data of the device the reading was taken from.
+/// * `ut` - Uncompensated temperature.
+///
 /// ### Returns
 ///
-/// The value of `temperature` and the calculated `b5` coefficient.
-pub fn calculate_temperature(calib_data: &CalibrationData, ut: i32) -> (f32, i32) {
-    let x1 = (ut as i32 - calib_data.ac6 as i32) * calib_data.ac5 as i32 >> 15;
-    let x2 = ((calib_data.mc as i32) << 11) / (x1 + calib_data.md as i32);
+/// The value of `temperature` and the calculated `b5` coefficient, needed by [`calculate_pressure`].
+pub fn calculate_temperature(
+    calib_data: &CalibrationData,
+    ut: i32,
+) -> Result<(f32, i32), CompensationError> {
+    if !calib_data.is_valid() {
+        return Err(CompensationError::InvalidCalibration);
+    }
+    if !(0..=UT_MAX).contains(&ut) {
+        return Err(CompensationError::TemperatureReadingOutOfRange);
+    }
+
+    // |x1| < 2^16 * 2^16 >> 15 = 2^17
+    let x1 = ((ut as i64 - calib_data.ac6 as i64) * calib_data.ac5 as i64) >> 15;
+    let divisor = x1 + calib_data.md as i64;
+    if divisor == 0 {
+        return Err(CompensationError::DivisionByZero);
+    }
+    // |x2| <= 2^15 << 11 = 2^26
+    let x2 = ((calib_data.mc as i64) << 11) / divisor;
     let b5 = x1 + x2;
     let temperature = ((b5 + 8) >> 4) as f32 / 10.0;
 
-    (temperature, b5)
+    Ok((temperature, b5 as i32))
 }
 
 /// Calculates pressure from uncompensated pressure value
 ///
+/// All intermediate values are computed in 64 bits, which can't overflow for any 16-bit
+/// calibration coefficients, `b5` coefficient obtained from [`calculate_temperature`] and
+/// readings of up to 19 bits.
+///
 /// ### Arguments
 ///
+/// * `calib_data` - Calibration data of the device the reading was taken from.
+/// * `oss` - Oversampling setting used to take the reading.
 /// * `b5` - B5 coefficient from temperature calculation.
 /// * `up` - Uncompensated pressure.
 ///
 /// ### Returns
 ///
 /// The value of `pressure`.
-pub fn calculate_pressure(calib_data: &CalibrationData, oss: u8, b5: i32, up: i32) -> Option<i32> {
-    let b6: i32 = b5 as i32 - 4000;
-    let x1 = calib_data.b2 as i32 * ((b6 * b6) >> 12) >> 11;
-    let x2 = (calib_data.ac2 as i32 * b6) >> 11;
+pub fn calculate_pressure(
+    calib_data: &CalibrationData,
+    oss: Oss,
+    b5: i32,
+    up: i32,
+) -> Result<i32, CompensationError> {
+    if !calib_data.is_valid() {
+        return Err(CompensationError::InvalidCalibration);
+    }
+    if !(-B5_LIMIT..=B5_LIMIT).contains(&b5) {
+        return Err(CompensationError::TemperatureReadingOutOfRange);
+    }
+    if !(0..=oss.max_uncompensated_pressure()).contains(&up) {
+        return Err(CompensationError::PressureReadingOutOfRange);
+    }
+
+    let oss = oss.val();
+    // |b6| < 2^27, so b6 * b6 < 2^54
+    let b6 = b5 as i64 - 4000;
+    // |x1| < 2^15 * 2^42 >> 11 = 2^46
+    let x1 = (calib_data.b2 as i64 * ((b6 * b6) >> 12)) >> 11;
+    // |x2| < 2^15 * 2^27 >> 11 = 2^31
+    let x2 = (calib_data.ac2 as i64 * b6) >> 11;
     let x3 = x1 + x2;
-    let b3 = (((calib_data.ac1 as i32 * 4 + x3) << oss) + 2) / 4;
-    let mut x1 = (calib_data.ac3 as i32 * b6) >> 13;
-    let mut x2 = calib_data.b1 as i32 * ((b6 * b6) >> 12) >> 16;
+    // |b3| < 2^47 << oss >> 2 = 2^(45 + oss)
+    let b3 = (((calib_data.ac1 as i64 * 4 + x3) << oss) + 2) / 4;
+    // |x1| < 2^15 * 2^27 >> 13 = 2^29
+    let x1 = (calib_data.ac3 as i64 * b6) >> 13;
+    // |x2| < 2^15 * 2^42 >> 16 = 2^41
+    let x2 = (calib_data.b1 as i64 * ((b6 * b6) >> 12)) >> 16;
     let x3 = (x1 + x2 + 2) >> 2;
-    let b4 = (calib_data.ac4 as u32 * (x3 as u32 + 0x8000)) >> 15;
-    let b7 = (up as u32).checked_sub(b3 as u32)? * (50_000 >> oss);
+    // |b4| < 2^16 * 2^40 >> 15 = 2^41
+    let b4 = (calib_data.ac4 as i64 * (x3 + 0x8000)) >> 15;
+    if b4 == 0 {
+        return Err(CompensationError::DivisionByZero);
+    }
+    // |b7| < 2^(45 + oss) * 2^16 >> oss = 2^61
+    let b7 = (up as i64 - b3) * (50_000 >> oss);
+    // Both are unsigned in the datasheet, a negative value means nonsensical coefficients
+    if b4 < 0 || b7 < 0 {
+        return Err(CompensationError::InvalidCalibration);
+    }
     let p = if b7 < 0x80000000 {
-        ((b7 as i64 * 2) / b4 as i64) as i32
+        (b7 * 2) / b4
     } else {
-        (b7 / b4 * 2) as i32
+        (b7 / b4) * 2
     };
-    x1 = (p >> 8).checked_mul(p >> 8)?;
-    x1 = (x1 * 3038) >> 16;
-    x2 = (-7357 * p) >> 16;
+    // Pressures that don't fit in an i32 can only come from nonsensical coefficients
+    if p > i32::MAX as i64 {
+        return Err(CompensationError::InvalidCalibration);
+    }
+    // |x1| < (2^23)^2 * 2^12 >> 16 = 2^42
+    let x1 = (((p >> 8) * (p >> 8)) * 3038) >> 16;
+    // |x2| < 2^31 * 2^13 >> 16 = 2^28
+    let x2 = (-7357 * p) >> 16;
     let pressure = p + ((x1 + x2 + 3791) >> 4);
 
-    Some(pressure)
+    i32::try_from(pressure).map_err(|_| CompensationError::InvalidCalibration)
+}
+
+/// Compensates a sample of uncompensated readings, as taken by [`BMP::read_raw_pressure`](crate::BMP::read_raw_pressure).
+///
+/// ### Arguments
+///
+/// * `calib_data` - Calibration data of the device the sample was taken from.
+/// * `sample` - Uncompensated readings.
+///
+/// ### Returns
+///
+/// The compensated temperature and pressure [`Measurement`].
+pub fn compensate(
+    calib_data: &CalibrationData,
+    sample: RawSample,
+) -> Result<Measurement, CompensationError> {
+    let up = i32::try_from(sample.up).map_err(|_| CompensationError::PressureReadingOutOfRange)?;
+    let (temperature, b5) = calculate_temperature(calib_data, sample.ut as i32)?;
+    let pressure = calculate_pressure(calib_data, sample.oss, b5, up)?;
+
+    Ok(Measurement {
+        temperature,
+        pressure,
+    })
+}
+
+/// Calculates temperature in ºC from uncompensated temperature value with the floating-point
+/// polynomial algorithm, checking the inputs like [`calculate_temperature`].
+fn calculate_temperature_float(
+    calib_data: &CalibrationData,
+    ut: i32,
+) -> Result<f64, CompensationError> {
+    if !calib_data.is_valid() {
+        return Err(CompensationError::InvalidCalibration);
+    }
+    if !(0..=UT_MAX).contains(&ut) {
+        return Err(CompensationError::TemperatureReadingOutOfRange);
+    }
+
+    let c5 = calib_data.ac5 as f64 / (32_768.0 * 160.0);
+    let mc = calib_data.mc as f64 * 2048.0 / (160.0 * 160.0);
+    let md = calib_data.md as f64 / 160.0;
+    let a = c5 * (ut as f64 - calib_data.ac6 as f64);
+    if a + md == 0.0 {
+        return Err(CompensationError::DivisionByZero);
+    }
+
+    Ok(a + mc / (a + md))
+}
+
+/// Compensates a sample of uncompensated readings with the floating-point polynomial algorithm, derived
+/// from the same calibration coefficients as the datasheet's integer algorithm.
+///
+/// The integer algorithm truncates at several steps, so that its output moves in steps of 0.1 ºC and of up
+/// to ~7 Pa between consecutive readings. The floating-point algorithm gives smoother output, deviating
+/// from the integer one by at most 0.1 ºC and 10 Pa over the operating range.
+///
+/// ### Arguments
+///
+/// * `calib_data` - Calibration data of the device the sample was taken from.
+/// * `sample` - Uncompensated readings.
+///
+/// ### Returns
+///
+/// The compensated temperature and pressure [`Measurement`].
+pub fn compensate_float(
+    calib_data: &CalibrationData,
+    sample: RawSample,
+) -> Result<Measurement, CompensationError> {
+    let up = i32::try_from(sample.up).map_err(|_| CompensationError::PressureReadingOutOfRange)?;
+    let temperature = calculate_temperature_float(calib_data, sample.ut as i32)?;
+    if !(0..=sample.oss.max_uncompensated_pressure()).contains(&up) {
+        return Err(CompensationError::PressureReadingOutOfRange);
+    }
+
+    let c3 = 160.0 * calib_data.ac3 as f64 / 32_768.0;
+    let c4 = calib_data.ac4 as f64 / (1000.0 * 32_768.0);
+    let b1 = 160.0 * 160.0 * calib_data.b1 as f64 / (1 << 30) as f64;
+    let x = |s: f64| {
+        let x1 = 160.0 * calib_data.ac2 as f64 / 8192.0;
+        let x2 = 160.0 * 160.0 * calib_data.b2 as f64 / (1 << 25) as f64;
+        x2 * s * s + x1 * s + calib_data.ac1 as f64
+    };
+    let y = |s: f64| c4 * b1 * s * s + c4 * c3 * s + c4 * 32_768.0;
+
+    let s = temperature - 25.0;
+    let divisor = y(s);
+    if divisor == 0.0 {
+        return Err(CompensationError::DivisionByZero);
+    }
+    // Reading in 16-bit units, keeping the extra resolution of higher oversampling settings
+    let pu = up as f64 / (1 << sample.oss.val()) as f64;
+    let z = (pu - x(s)) / divisor;
+    // Polynomial in hPa
+    let p0 = (3791.0 - 8.0) / 1600.0;
+    let p1 = 1.0 - 7357.0 / (1 << 20) as f64;
+    let p2 = 3038.0 * 100.0 / (1u64 << 36) as f64;
+    let pressure = libm::round((p2 * z * z + p1 * z + p0) * 100.0);
+    // Negative or huge pressures can only come from nonsensical coefficients
+    if !(0.0..=i32::MAX as f64).contains(&pressure) {
+        return Err(CompensationError::InvalidCalibration);
+    }
+
+    Ok(Measurement {
+        temperature: temperature as f32,
+        pressure: pressure as i32,
+    })
+}
+
+/// Compensates a sample of uncompensated readings with the given algorithm, see [`compensate`] and
+/// [`compensate_float`].
+///
+/// ### Arguments
+///
+/// * `algorithm` - Compensation algorithm to use.
+/// * `calib_data` - Calibration data of the device the sample was taken on.
+/// * `sample` - Uncompensated temperature and pressure, with the oversampling setting used.
+///
+/// ### Returns
+///
+/// Compensated [`Measurement`], or [`CompensationError`] if the calibration data can't compensate the sample
+pub fn compensate_with(
+    algorithm: CompensationAlgorithm,
+    calib_data: &CalibrationData,
+    sample: RawSample,
+) -> Result<Measurement, CompensationError> {
+    match algorithm {
+        CompensationAlgorithm::Datasheet => compensate(calib_data, sample),
+        CompensationAlgorithm::FloatingPoint => compensate_float(calib_data, sample),
+    }
+}
+
+/// Calculates temperature from uncompensated temperature value with the given algorithm, see
+/// [`calculate_temperature`] and [`compensate_float`].
+///
+/// ### Arguments
+///
+/// * `algorithm` - Compensation algorithm to use.
+/// * `calib_data` - Calibration data of the device the value was read on.
+/// * `ut` - Uncompensated temperature value.
+///
+/// ### Returns
+///
+/// `temperature` in degrees Celsius (ºC), or [`CompensationError`] if the calibration data can't compensate
+/// the value
+pub fn calculate_temperature_with(
+    algorithm: CompensationAlgorithm,
+    calib_data: &CalibrationData,
+    ut: i32,
+) -> Result<f32, CompensationError> {
+    match algorithm {
+        CompensationAlgorithm::Datasheet => {
+            calculate_temperature(calib_data, ut).map(|(temperature, _)| temperature)
+        }
+        CompensationAlgorithm::FloatingPoint => {
+            calculate_temperature_float(calib_data, ut).map(|temperature| temperature as f32)
+        }
+    }
 }
 
+/// Calculates altitude from pressure with the international barometric formula.
+///
+/// ### Arguments
+///
+/// * `pressure` - Pressure in pascals (Pa).
+/// * `sea_level_pressure` - Pressure at sea level in pascals (Pa).
+///
+/// ### Returns
+///
+/// `altitude` in meters (m)
 pub fn calculate_altitude(pressure: i32, sea_level_pressure: i32) -> f32 {
     let p_sea_level_ratio: f32 = pressure as f32 / sea_level_pressure as f32;
-    let altitude = 44_330.0 * (1.0 - libm::powf(p_sea_level_ratio, 1.0 / 5.255));
-    altitude
+    44_330.0 * (1.0 - libm::powf(p_sea_level_ratio, 1.0 / 5.255))
+}
+
+/// Calculates the pressure found at an altitude, the inverse of [`calculate_altitude`], e.g. to turn
+/// altitude thresholds into pressure thresholds once rather than converting every reading.
+///
+/// ### Arguments
+///
+/// * `altitude` - Altitude in meters (m).
+/// * `sea_level_pressure` - Pressure at sea level in pascals (Pa).
+///
+/// ### Returns
+///
+/// `pressure` in pascals (Pa), not rounded so that converting it back gives `altitude`. The formula reaches
+/// zero pressure at 44 330 m, so `0.0` at and above that altitude
+pub fn pressure_at_altitude(altitude: f32, sea_level_pressure: i32) -> f32 {
+    sea_level_pressure as f32 * pressure_ratio_at_altitude(altitude)
+}
+
+/// Fraction of the sea level pressure found at `altitude` (in m), see [`pressure_at_altitude`].
+pub(crate) fn pressure_ratio_at_altitude(altitude: f32) -> f32 {
+    libm::powf((1.0 - altitude / 44_330.0).max(0.0), 5.255)
+}
+
+/// Calculates the altitude in the standard atmosphere at which `pressure` is found, i.e. relative to
+/// a sea level pressure of 1013.25 hPa.
+pub fn calculate_pressure_altitude(pressure: i32) -> f32 {
+    calculate_altitude(pressure, DEFAULT_SEA_LEVEL_PESSURE)
+}
+
+/// Calculates the density of dry air with the ideal gas law.
+///
+/// ### Arguments
+///
+/// * `pressure` - Pressure in pascals (Pa).
+/// * `temperature` - Temperature in degrees Celsius (ºC).
+///
+/// ### Returns
+///
+/// Air density in kg/m³
+pub fn calculate_air_density(pressure: i32, temperature: f32) -> f32 {
+    pressure as f32 / (DRY_AIR_GAS_CONSTANT * (temperature + ZERO_CELSIUS))
+}
+
+/// Calculates the altitude in the standard atmosphere at which the density of dry air at `pressure`
+/// and `temperature` is found.
+///
+/// ### Arguments
+///
+/// * `pressure` - Pressure in pascals (Pa).
+/// * `temperature` - Temperature in degrees Celsius (ºC).
+///
+/// ### Returns
+///
+/// Density altitude in meters (m)
+pub fn calculate_density_altitude(pressure: i32, temperature: f32) -> f32 {
+    let density_ratio = calculate_air_density(pressure, temperature) / ISA_SEA_LEVEL_DENSITY;
+    // Density in the standard atmosphere is proportional to (1 - L·h / T0)^(g / (R·L) - 1)
+    let exponent = STANDARD_GRAVITY / (DRY_AIR_GAS_CONSTANT * LAPSE_RATE) - 1.0;
+    ISA_SEA_LEVEL_TEMPERATURE / LAPSE_RATE * (1.0 - libm::powf(density_ratio, 1.0 / exponent))
+}
+
+/// Checks that no two `measurements` differ by more than `max_temperature_spread` (in ºC)
+/// or `max_pressure_spread` (in Pa).
+pub(crate) fn measurements_are_consistent<'a>(
+    measurements: impl Iterator<Item = &'a Measurement> + Clone,
+    max_temperature_spread: f32,
+    max_pressure_spread: i32,
+) -> bool {
+    let temperatures = measurements.clone().map(|m| m.temperature);
+    let min_temperature = temperatures.clone().fold(f32::INFINITY, f32::min);
+    let max_temperature = temperatures.fold(f32::NEG_INFINITY, f32::max);
+    let pressures = measurements.map(|m| m.pressure);
+    let (Some(min_pressure), Some(max_pressure)) = (pressures.clone().min(), pressures.max())
+    else {
+        return true;
+    };
+
+    max_temperature - min_temperature <= max_temperature_spread
+        && max_pressure - min_pressure <= max_pressure_spread
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::simulator::{uncompensated_pressure, uncompensated_temperature};
+    use proptest::prelude::*;
 
     const CALIB_DATA: CalibrationData = CalibrationData {
         ac1: 408,
@@ -75,15 +400,55 @@ mod tests {
     #[test]
     fn calculates_temperature_correctly() {
         let ut = 27898;
-        let (temperature, b5) = calculate_temperature(&CALIB_DATA, ut);
+        let (temperature, b5) = calculate_temperature(&CALIB_DATA, ut).unwrap();
 
         assert!((temperature - 15.0).abs() < 0.1);
         assert!((b5 - 2399).abs() <= 1)
     }
 
+    #[test]
+    fn fails_calculating_temperature_given_out_of_range_reading() {
+        assert_eq!(
+            calculate_temperature(&CALIB_DATA, 0x10000),
+            Err(CompensationError::TemperatureReadingOutOfRange)
+        );
+        assert_eq!(
+            calculate_temperature(&CALIB_DATA, -1),
+            Err(CompensationError::TemperatureReadingOutOfRange)
+        );
+    }
+
+    #[test]
+    fn fails_calculating_temperature_given_zero_divisor() {
+        // With ac5 = 2^15, x1 = ut - ac6, so the divisor x1 + md vanishes at ut = ac6 - md
+        let calib_data = CalibrationData {
+            ac5: 0x8000,
+            ..CALIB_DATA
+        };
+        let ut = calib_data.ac6 as i32 - calib_data.md as i32;
+
+        assert_eq!(
+            calculate_temperature(&calib_data, ut),
+            Err(CompensationError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn fails_calculating_temperature_given_unprogrammed_calib_data() {
+        let calib_data = CalibrationData {
+            md: -1,
+            ..CALIB_DATA
+        };
+
+        assert_eq!(
+            calculate_temperature(&calib_data, 27898),
+            Err(CompensationError::InvalidCalibration)
+        );
+    }
+
     #[test]
     fn calculates_pressure_correctly() {
-        let oss = 0;
+        let oss = Oss::LowPower;
         let b5 = 2399;
         let up = 23_843;
         let pressure = calculate_pressure(&CALIB_DATA, oss, b5, up);
@@ -93,7 +458,7 @@ mod tests {
 
     #[test]
     fn calculates_pressure_correctly_oss_1() {
-        let oss = 1;
+        let oss = Oss::Standard;
         let b5 = 2399;
         let up = 47_686;
         let pressure = calculate_pressure(&CALIB_DATA, oss, b5, up);
@@ -103,7 +468,7 @@ mod tests {
 
     #[test]
     fn calculates_pressure_correctly_oss_3() {
-        let oss = 3;
+        let oss = Oss::UltraHighRes;
         let b5 = 2399;
         let up = 190_744;
         let pressure = calculate_pressure(&CALIB_DATA, oss, b5, up);
@@ -113,7 +478,7 @@ mod tests {
 
     #[test]
     fn fails_calculating_pressure_given_invalid_calib_data() {
-        let oss = 0;
+        let oss = Oss::LowPower;
         let b5 = 2399;
         let up = 23_843;
         let calib_data = CalibrationData {
@@ -122,7 +487,119 @@ mod tests {
         };
         let pressure = calculate_pressure(&calib_data, oss, b5, up);
 
-        assert!(pressure.is_none());
+        assert_eq!(pressure, Err(CompensationError::InvalidCalibration));
+    }
+
+    #[test]
+    fn fails_calculating_pressure_given_out_of_range_reading() {
+        let b5 = 2399;
+
+        assert_eq!(
+            calculate_pressure(&CALIB_DATA, Oss::LowPower, b5, 0x10000),
+            Err(CompensationError::PressureReadingOutOfRange)
+        );
+        assert_eq!(
+            calculate_pressure(&CALIB_DATA, Oss::UltraHighRes, b5, 0x80000),
+            Err(CompensationError::PressureReadingOutOfRange)
+        );
+        assert_eq!(
+            calculate_pressure(&CALIB_DATA, Oss::LowPower, b5, -1),
+            Err(CompensationError::PressureReadingOutOfRange)
+        );
+    }
+
+    #[test]
+    fn fails_calculating_pressure_given_impossible_b5() {
+        assert_eq!(
+            calculate_pressure(&CALIB_DATA, Oss::LowPower, i32::MAX, 23_843),
+            Err(CompensationError::TemperatureReadingOutOfRange)
+        );
+    }
+
+    #[test]
+    fn fails_calculating_pressure_given_zero_divisor() {
+        // b4 = ac4 * (x3 + 2^15) >> 15 vanishes for any negative x3 if ac4 = 1
+        let calib_data = CalibrationData {
+            ac4: 1,
+            ..CALIB_DATA
+        };
+
+        assert_eq!(
+            calculate_pressure(&calib_data, Oss::LowPower, 6000, 23_843),
+            Err(CompensationError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn compensates_raw_sample() {
+        let sample = RawSample {
+            ut: 27898,
+            up: 23_843,
+            oss: Oss::LowPower,
+        };
+        let measurement = compensate(&CALIB_DATA, sample).unwrap();
+
+        assert!((measurement.temperature - 15.0).abs() < 0.1);
+        assert_eq!(measurement.pressure, 69964);
+    }
+
+    #[test]
+    fn compensates_raw_sample_with_floating_point_algorithm() {
+        let sample = RawSample {
+            ut: 27898,
+            up: 23_843,
+            oss: Oss::LowPower,
+        };
+        let measurement = compensate_float(&CALIB_DATA, sample).unwrap();
+
+        assert!((measurement.temperature - 15.0).abs() < 0.01);
+        assert!((measurement.pressure - 69964).abs() <= 2);
+        assert_eq!(
+            compensate_with(CompensationAlgorithm::FloatingPoint, &CALIB_DATA, sample),
+            Ok(measurement)
+        );
+    }
+
+    #[test]
+    fn fails_compensating_with_floating_point_algorithm_given_invalid_inputs() {
+        let sample = RawSample {
+            ut: 27898,
+            up: u32::MAX,
+            oss: Oss::UltraHighRes,
+        };
+        assert_eq!(
+            compensate_float(&CALIB_DATA, sample),
+            Err(CompensationError::PressureReadingOutOfRange)
+        );
+
+        // Same vanishing divisor as the integer algorithm, with ac5 = 2^15
+        let calib_data = CalibrationData {
+            ac5: 0x8000,
+            ..CALIB_DATA
+        };
+        let sample = RawSample {
+            ut: (calib_data.ac6 as i32 - calib_data.md as i32) as u16,
+            up: 23_843,
+            oss: Oss::LowPower,
+        };
+        assert_eq!(
+            compensate_float(&calib_data, sample),
+            Err(CompensationError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn fails_compensating_raw_sample_given_out_of_range_pressure() {
+        let sample = RawSample {
+            ut: 27898,
+            up: u32::MAX,
+            oss: Oss::UltraHighRes,
+        };
+
+        assert_eq!(
+            compensate(&CALIB_DATA, sample),
+            Err(CompensationError::PressureReadingOutOfRange)
+        );
     }
 
     #[test]
@@ -133,4 +610,266 @@ mod tests {
 
         assert!((altitude - 645.0).abs() < 0.5);
     }
+
+    /// Pressure in Pa, temperature in ºC and density in kg/m³ of the ICAO standard atmosphere,
+    /// at altitudes in m.
+    const ICAO_STANDARD_ATMOSPHERE: [(f32, i32, f32, f32); 5] = [
+        (0.0, 101_325, 15.0, 1.2250),
+        (1000.0, 89_875, 8.5, 1.1117),
+        (2000.0, 79_495, 2.0, 1.0066),
+        (3000.0, 70_121, -4.5, 0.9093),
+        (5000.0, 54_048, -17.5, 0.7364),
+    ];
+
+    #[test]
+    fn calculates_air_density_correctly() {
+        for (_, pressure, temperature, density) in ICAO_STANDARD_ATMOSPHERE {
+            let calculated = calculate_air_density(pressure, temperature);
+            assert!(
+                (calculated - density).abs() < 0.001,
+                "{calculated} != {density}"
+            );
+        }
+    }
+
+    #[test]
+    fn calculates_pressure_and_density_altitudes_correctly() {
+        for (altitude, pressure, temperature, _) in ICAO_STANDARD_ATMOSPHERE {
+            // In the standard atmosphere, both are the geometric altitude
+            assert!((calculate_pressure_altitude(pressure) - altitude).abs() < 5.0);
+            assert!((calculate_density_altitude(pressure, temperature) - altitude).abs() < 10.0);
+        }
+    }
+
+    #[test]
+    fn density_altitude_increases_with_temperature() {
+        // A hot day at sea level, 20 ºC above standard: about 120 ft per ºC
+        let density_altitude = calculate_density_altitude(101_325, 35.0);
+
+        assert!((density_altitude - 731.0).abs() < 50.0);
+        assert!(calculate_density_altitude(101_325, -5.0) < 0.0);
+    }
+
+    #[test]
+    fn calculates_pressure_at_altitude_correctly() {
+        let pressure = pressure_at_altitude(645.0, 101_325);
+
+        assert!((pressure - 93_810.0).abs() < 10.0);
+        assert_eq!(pressure_at_altitude(0.0, 101_325), 101_325.0);
+        assert_eq!(pressure_at_altitude(44_330.0, 101_325), 0.0);
+        assert_eq!(pressure_at_altitude(50_000.0, 101_325), 0.0);
+    }
+
+    #[test]
+    fn checks_measurements_consistency() {
+        let measurements = [
+            Measurement {
+                temperature: 15.0,
+                pressure: 69_964,
+            },
+            Measurement {
+                temperature: 15.5,
+                pressure: 70_010,
+            },
+        ];
+
+        assert!(measurements_are_consistent(measurements.iter(), 1.0, 50));
+        assert!(!measurements_are_consistent(measurements.iter(), 0.4, 50));
+        assert!(!measurements_are_consistent(measurements.iter(), 1.0, 40));
+        assert!(measurements_are_consistent([].iter(), 0.0, 0));
+    }
+
+    #[test]
+    fn simulator_inverts_datasheet_example() {
+        let ut = uncompensated_temperature(&CALIB_DATA, 15.0).unwrap();
+        let up = uncompensated_pressure(&CALIB_DATA, Oss::LowPower, 2399, 69964).unwrap();
+
+        assert_eq!(ut, 27898);
+        assert_eq!(up, 23843);
+    }
+
+    fn oss() -> impl Strategy<Value = Oss> {
+        prop_oneof![
+            Just(Oss::LowPower),
+            Just(Oss::Standard),
+            Just(Oss::HighRes),
+            Just(Oss::UltraHighRes),
+        ]
+    }
+
+    /// Calibration sets spanning the coefficients reported by real BMP085/BMP180 devices.
+    fn calibration_data() -> impl Strategy<Value = CalibrationData> {
+        (
+            (408i16..=9000, -1500i16..=-72, -14900i16..=-13900),
+            (31000u16..=34500, 24000u16..=32757, 14000u16..=23153),
+            (5400i16..=6600, 4i16..=60, -11800i16..=-8700, 2300i16..=2900),
+        )
+            .prop_map(|((ac1, ac2, ac3), (ac4, ac5, ac6), (b1, b2, mc, md))| {
+                CalibrationData {
+                    ac1,
+                    ac2,
+                    ac3,
+                    ac4,
+                    ac5,
+                    ac6,
+                    b1,
+                    b2,
+                    mb: -32768,
+                    mc,
+                    md,
+                }
+            })
+    }
+
+    /// Any calibration set the EEPROM could hold, valid or not.
+    fn any_calibration_data() -> impl Strategy<Value = CalibrationData> {
+        (
+            (any::<i16>(), any::<i16>(), any::<i16>(), any::<u16>()),
+            (any::<u16>(), any::<u16>(), any::<i16>(), any::<i16>()),
+            (any::<i16>(), any::<i16>(), any::<i16>()),
+        )
+            .prop_map(|((ac1, ac2, ac3, ac4), (ac5, ac6, b1, b2), (mb, mc, md))| {
+                CalibrationData {
+                    ac1,
+                    ac2,
+                    ac3,
+                    ac4,
+                    ac5,
+                    ac6,
+                    b1,
+                    b2,
+                    mb,
+                    mc,
+                    md,
+                }
+            })
+    }
+
+    /// Maximum deviation in Pa of recovered pressures. The integer algorithm can step by up to ~7 Pa
+    /// between consecutive readings: ~3 Pa per UP at the lowest oversampling setting, plus ~3 Pa
+    /// when `p >> 8` increments in the second-order correction.
+    const PRESSURE_TOLERANCE: i32 = 4;
+
+    /// Maximum deviations between the integer and floating-point algorithms, as documented in [`compensate_float`].
+    const ALGORITHM_TEMPERATURE_DEVIATION: f32 = 0.1;
+    const ALGORITHM_PRESSURE_DEVIATION: i32 = 10;
+
+    proptest! {
+        #[test]
+        fn temperature_round_trips_through_simulator(
+            calib_data in calibration_data(),
+            temperature in -40.0f32..85.0,
+        ) {
+            let ut = uncompensated_temperature(&calib_data, temperature);
+            prop_assert!(ut.is_some());
+            let (recovered, _) = calculate_temperature(&calib_data, ut.unwrap()).unwrap();
+
+            prop_assert!((recovered - temperature).abs() <= 0.1 + f32::EPSILON * 100.0);
+        }
+
+        #[test]
+        fn pressure_round_trips_through_simulator(
+            calib_data in calibration_data(),
+            temperature in -40.0f32..85.0,
+            pressure in 30_000i32..=110_000,
+            oss in oss(),
+        ) {
+            let ut = uncompensated_temperature(&calib_data, temperature);
+            prop_assert!(ut.is_some());
+            let (_, b5) = calculate_temperature(&calib_data, ut.unwrap()).unwrap();
+            let up = uncompensated_pressure(&calib_data, oss, b5, pressure);
+            prop_assert!(up.is_some());
+            let recovered = calculate_pressure(&calib_data, oss, b5, up.unwrap());
+
+            prop_assert!(recovered.is_ok());
+            prop_assert!((recovered.unwrap() - pressure).abs() <= PRESSURE_TOLERANCE);
+        }
+
+        #[test]
+        fn algorithms_agree_over_operating_range(
+            calib_data in calibration_data(),
+            temperature in -40.0f32..85.0,
+            pressure in 30_000i32..=110_000,
+            oss in oss(),
+        ) {
+            let ut = uncompensated_temperature(&calib_data, temperature);
+            prop_assert!(ut.is_some());
+            let (_, b5) = calculate_temperature(&calib_data, ut.unwrap()).unwrap();
+            let up = uncompensated_pressure(&calib_data, oss, b5, pressure);
+            prop_assert!(up.is_some());
+            let sample = RawSample {
+                ut: ut.unwrap() as u16,
+                up: up.unwrap() as u32,
+                oss,
+            };
+            let datasheet = compensate(&calib_data, sample).unwrap();
+            let float = compensate_float(&calib_data, sample);
+
+            prop_assert!(float.is_ok());
+            let float = float.unwrap();
+            prop_assert!(
+                (datasheet.temperature - float.temperature).abs() <= ALGORITHM_TEMPERATURE_DEVIATION
+            );
+            prop_assert!((datasheet.pressure - float.pressure).abs() <= ALGORITHM_PRESSURE_DEVIATION);
+        }
+
+        #[test]
+        fn pressure_is_computed_for_any_reading(
+            calib_data in calibration_data(),
+            temperature in -40.0f32..85.0,
+            oss in oss(),
+            up_ratio in 0.0f64..=1.0,
+        ) {
+            let ut = uncompensated_temperature(&calib_data, temperature);
+            prop_assert!(ut.is_some());
+            let (_, b5) = calculate_temperature(&calib_data, ut.unwrap()).unwrap();
+            let up = (oss.max_uncompensated_pressure() as f64 * up_ratio) as i32;
+            let pressure = calculate_pressure(&calib_data, oss, b5, up);
+
+            // Readings below B3 would mean a negative pressure, anything else must compensate
+            prop_assert!(pressure.is_ok() || pressure == Err(CompensationError::InvalidCalibration));
+        }
+
+        #[test]
+        fn altitude_round_trips_through_pressure(
+            pressure in 30_000i32..=110_000,
+            sea_level_pressure in 95_000i32..=105_000,
+        ) {
+            let altitude = calculate_altitude(pressure, sea_level_pressure);
+            let recovered = pressure_at_altitude(altitude, sea_level_pressure);
+
+            prop_assert!((recovered - pressure as f32).abs() <= 0.05);
+        }
+
+        #[test]
+        fn pressure_round_trips_through_altitude(
+            altitude in -500.0f32..9000.0,
+            sea_level_pressure in 95_000i32..=105_000,
+        ) {
+            let pressure = pressure_at_altitude(altitude, sea_level_pressure);
+            // Pressures are measured in whole Pa, rounding by half a Pa shifts altitudes by up to 0.11 m at 9 km
+            let recovered = calculate_altitude(libm::roundf(pressure) as i32, sea_level_pressure);
+
+            prop_assert!((recovered - altitude).abs() <= 0.15);
+        }
+
+        #[test]
+        fn compensation_never_panics(
+            calib_data in any_calibration_data(),
+            ut in -1i32..=0x10000,
+            up in -1i32..=0x80000,
+            b5 in any::<i32>(),
+            oss in oss(),
+        ) {
+            // Any combination of inputs must produce a value or an error, without overflowing
+            let _ = calculate_temperature(&calib_data, ut);
+            let _ = calculate_pressure(&calib_data, oss, b5, up);
+            if let Ok((_, b5)) = calculate_temperature(&calib_data, ut) {
+                let _ = calculate_pressure(&calib_data, oss, b5, up);
+            }
+            if let (Ok(ut), Ok(up)) = (u16::try_from(ut), u32::try_from(up)) {
+                let _ = compensate_float(&calib_data, RawSample { ut, up, oss });
+            }
+        }
+    }
 }
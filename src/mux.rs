@@ -0,0 +1,129 @@
+//! Support for TCA9548A/PCA9548 I2C multiplexers, enabled with the `mux` feature.
+//!
+//! Every BMP085/BMP180 has the same fixed address, so several devices on one bus need a multiplexer.
+//! [`Tca9548a`] owns the bus, and hands out a [`MuxChannel`] per channel, which implements the
+//! `embedded-hal` I2C traits and selects its channel before each transaction when needed:
+//!
+//! ```ignore
+//! let mux = Tca9548a::new(i2c, 0x70);
+//! let indoor = BMP::new(mux.channel(0), delay, Default::default()).init()?;
+//! let outdoor = BMP::new(mux.channel(1), delay, Default::default()).init()?;
+//! ```
+
+use core::cell::Cell;
+#[cfg(not(feature = "async"))]
+use core::cell::{RefCell, RefMut};
+
+use crate::scan::MUX_CHANNELS;
+
+#[cfg(feature = "async")]
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+#[cfg(feature = "async")]
+use embassy_sync::mutex::{Mutex, MutexGuard};
+#[cfg(not(feature = "async"))]
+use embedded_hal::i2c::I2c;
+use embedded_hal::i2c::{ErrorType, Operation};
+#[cfg(feature = "async")]
+use embedded_hal_async::i2c::I2c as AsyncI2c;
+
+/// TCA9548A/PCA9548 I2C multiplexer, sharing its bus between the devices on each channel.
+///
+/// With the `async` feature, channels may be used concurrently from futures of the same executor,
+/// e.g. joined together: a transaction on one channel waits for the one in progress on another channel.
+pub struct Tca9548a<I2C> {
+    #[cfg(not(feature = "async"))]
+    bus: RefCell<I2C>,
+    #[cfg(feature = "async")]
+    bus: Mutex<NoopRawMutex, I2C>,
+    address: u8,
+    selected: Cell<Option<u8>>,
+}
+
+impl<I2C> Tca9548a<I2C> {
+    /// Creates a new multiplexer.
+    ///
+    /// ### Arguments
+    ///
+    /// * `i2c` - A properly initialized/configured `embedded-hal` I2C peripheral.
+    /// * `address` - Address of the multiplexer, `0x70` to `0x77` depending on its address pins.
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        Tca9548a {
+            #[cfg(not(feature = "async"))]
+            bus: RefCell::new(i2c),
+            #[cfg(feature = "async")]
+            bus: Mutex::new(i2c),
+            address,
+            selected: Cell::new(None),
+        }
+    }
+
+    /// Get an I2C bus for the devices on `channel`.
+    ///
+    /// ### Arguments
+    ///
+    /// * `channel` - Multiplexer channel, `0` to `7`.
+    ///
+    /// ### Returns
+    ///
+    /// [`MuxChannel`] implementing the `embedded-hal` I2C traits
+    pub fn channel(&self, channel: u8) -> MuxChannel<'_, I2C> {
+        assert!(channel < MUX_CHANNELS);
+        MuxChannel { mux: self, channel }
+    }
+
+    /// Release the I2C bus.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// The I2C bus the multiplexer was created with
+    pub fn release(self) -> I2C {
+        self.bus.into_inner()
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn lock(&self) -> RefMut<'_, I2C> {
+        self.bus.borrow_mut()
+    }
+
+    #[cfg(feature = "async")]
+    async fn lock(&self) -> MutexGuard<'_, NoopRawMutex, I2C> {
+        self.bus.lock().await
+    }
+}
+
+/// I2C bus for the devices on one channel of a [`Tca9548a`] multiplexer.
+pub struct MuxChannel<'a, I2C> {
+    mux: &'a Tca9548a<I2C>,
+    channel: u8,
+}
+
+impl<I2C: ErrorType> ErrorType for MuxChannel<'_, I2C> {
+    type Error = I2C::Error;
+}
+
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")), keep_self, idents(AsyncI2c(sync = "I2c"))),
+    async(feature = "async", keep_self)
+)]
+impl<I2C: AsyncI2c> AsyncI2c for MuxChannel<'_, I2C> {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        // The bus is locked for the whole transaction, so that no other channel can be selected halfway
+        let mut bus = self.mux.lock().await;
+        if self.mux.selected.get() != Some(self.channel) {
+            // Until the selection is acknowledged, which channels are enabled is unknown
+            self.mux.selected.set(None);
+            bus.write(self.mux.address, &[1 << self.channel]).await?;
+            self.mux.selected.set(Some(self.channel));
+        }
+
+        bus.transaction(address, operations).await
+    }
+}
@@ -0,0 +1,194 @@
+//! Continuous sampling for `embassy`, enabled with the `embassy` feature.
+//!
+//! A [`Sampler`] takes a measurement every configured period, and publishes it through any
+//! [`Publisher`], such as an `embassy-sync` [`Signal`], [`Channel`] or [`PubSubChannel`].
+//! Sampling is controlled from other tasks with a [`SamplerControl`]:
+//!
+//! ```ignore
+//! static CONTROL: SamplerControl<CriticalSectionRawMutex> = SamplerControl::new(SamplerConfig::DEFAULT);
+//! static MEASUREMENTS: Channel<CriticalSectionRawMutex, Measurement, 4> = Channel::new();
+//!
+//! #[embassy_executor::task]
+//! async fn sample(bmp: BMP<I2c<'static, I2C0, Async>, Delay>) {
+//!     Sampler::new(bmp).run(&CONTROL, &MEASUREMENTS).await
+//! }
+//! ```
+
+use core::cell::Cell;
+
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::channel::Channel;
+use embassy_sync::pubsub::{PubSubBehavior, PubSubChannel};
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::I2c;
+
+use crate::types::{Measurement, Oss, BMP};
+
+/// Destination of the measurements taken by a [`Sampler`].
+pub trait Publisher {
+    /// Publishes `measurement`, without waiting for it to be received.
+    fn publish(&self, measurement: Measurement);
+}
+
+impl<M: RawMutex> Publisher for Signal<M, Measurement> {
+    /// Replaces any measurement that wasn't received yet.
+    fn publish(&self, measurement: Measurement) {
+        self.signal(measurement);
+    }
+}
+
+impl<M: RawMutex, const N: usize> Publisher for Channel<M, Measurement, N> {
+    /// Drops `measurement` if the channel is full.
+    fn publish(&self, measurement: Measurement) {
+        let _ = self.try_send(measurement);
+    }
+}
+
+impl<M: RawMutex, const CAP: usize, const SUBS: usize, const PUBS: usize> Publisher
+    for PubSubChannel<M, Measurement, CAP, SUBS, PUBS>
+{
+    /// Subscribers lagging behind miss the oldest measurements.
+    fn publish(&self, measurement: Measurement) {
+        self.publish_immediate(measurement);
+    }
+}
+
+/// Sampling rate and oversampling setting of a [`Sampler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SamplerConfig {
+    /// Time between the start of consecutive measurements.
+    pub period: Duration,
+    /// Oversampling setting of the measurements.
+    pub oss: Oss,
+}
+
+impl SamplerConfig {
+    /// A measurement every second, with the [`LowPower`](Oss::LowPower) oversampling setting.
+    pub const DEFAULT: SamplerConfig = SamplerConfig {
+        period: Duration::from_secs(1),
+        oss: Oss::LowPower,
+    };
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        SamplerConfig::DEFAULT
+    }
+}
+
+#[derive(Clone, Copy)]
+struct SamplerState {
+    running: bool,
+    config: SamplerConfig,
+}
+
+/// Controls a [`Sampler`] from other tasks. Changes take effect immediately, even halfway through a period.
+pub struct SamplerControl<M: RawMutex> {
+    state: Mutex<M, Cell<SamplerState>>,
+    changed: Signal<M, ()>,
+}
+
+impl<M: RawMutex> SamplerControl<M> {
+    /// Creates a new control, for a sampler that starts running with `config`.
+    pub const fn new(config: SamplerConfig) -> Self {
+        SamplerControl {
+            state: Mutex::new(Cell::new(SamplerState {
+                running: true,
+                config,
+            })),
+            changed: Signal::new(),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut SamplerState)) {
+        self.state.lock(|state| {
+            let mut new_state = state.get();
+            f(&mut new_state);
+            state.set(new_state);
+        });
+        self.changed.signal(());
+    }
+
+    fn state(&self) -> SamplerState {
+        self.state.lock(Cell::get)
+    }
+
+    /// Resumes sampling, taking a measurement right away.
+    pub fn start(&self) {
+        self.update(|state| state.running = true);
+    }
+
+    /// Pauses sampling, until [`start`](SamplerControl::start) is called.
+    pub fn stop(&self) {
+        self.update(|state| state.running = false);
+    }
+
+    /// Replaces the sampler's configuration. A running sampler takes a measurement right away.
+    pub fn reconfigure(&self, config: SamplerConfig) {
+        self.update(|state| state.config = config);
+    }
+
+    /// Get whether sampling is running.
+    pub fn is_running(&self) -> bool {
+        self.state().running
+    }
+
+    /// Get the sampler's current configuration.
+    pub fn config(&self) -> SamplerConfig {
+        self.state().config
+    }
+}
+
+/// Takes measurements with a [`BMP`] driver at a configured rate.
+pub struct Sampler<I2C, D> {
+    bmp: BMP<I2C, D>,
+}
+
+impl<I2C, D> Sampler<I2C, D>
+where
+    I2C: I2c,
+    D: DelayNs,
+{
+    /// Creates a new sampler, taking ownership of the driver.
+    pub fn new(bmp: BMP<I2C, D>) -> Self {
+        Sampler { bmp }
+    }
+
+    /// Get the driver back, e.g. to check its [`health`](BMP::health).
+    pub fn release(self) -> BMP<I2C, D> {
+        self.bmp
+    }
+
+    /// Sample as configured by `control`, publishing every successful measurement to `publisher`.
+    /// Failed measurements are skipped, and reflected in the driver's [`health`](BMP::health).
+    ///
+    /// Never returns, cancel the future (i.e. with `embassy_futures::select`) to get the driver back.
+    pub async fn run<M: RawMutex, P: Publisher>(
+        &mut self,
+        control: &SamplerControl<M>,
+        publisher: &P,
+    ) {
+        loop {
+            let state = control.state();
+            if !state.running {
+                control.changed.wait().await;
+                continue;
+            }
+
+            let started = Instant::now();
+            self.bmp.set_oversampling_setting(state.config.oss);
+            if let Ok(measurement) = self.bmp.read_measurement().await {
+                // Sampling may have been stopped while the measurement was taken
+                if control.is_running() {
+                    publisher.publish(measurement);
+                }
+            }
+
+            let next = started + state.config.period;
+            embassy_futures::select::select(Timer::at(next), control.changed.wait()).await;
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Helpers to find BMP085/BMP180 devices on a bus, or behind a TCA9548A/PCA9548 I2C multiplexer.
+
+use crate::constants::{BMP_DEVICE_ID, BMP_ID_REG};
+
+#[cfg(not(feature = "async"))]
+use embedded_hal::i2c::I2c;
+use embedded_hal::i2c::{Error, ErrorKind};
+#[cfg(feature = "async")]
+use embedded_hal_async::i2c::I2c as AsyncI2c;
+
+/// Number of channels of a TCA9548A/PCA9548 multiplexer.
+pub const MUX_CHANNELS: u8 = 8;
+
+/// Check whether a BMP085/BMP180 device answers at `address`, by reading its chip identifier.
+/// Since the device address is fixed, scanning a bus amounts to probing the default address `0x77`.
+///
+/// ### Arguments
+///
+/// * `i2c` - I2C bus to probe.
+/// * `address` - Address to probe.
+///
+/// ### Returns
+///
+/// `true` if a device with a BMP085/BMP180 chip identifier answered, `false` if nothing answered or
+/// another kind of device did. Bus errors other than a NACK are returned as is.
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")), keep_self, idents(AsyncI2c(sync = "I2c"))),
+    async(feature = "async", keep_self)
+)]
+pub async fn probe<I2C: AsyncI2c>(i2c: &mut I2C, address: u8) -> Result<bool, I2C::Error> {
+    let mut id = [0];
+    match i2c.write_read(address, &[BMP_ID_REG], &mut id).await {
+        Ok(()) => Ok(id[0] == BMP_DEVICE_ID),
+        Err(err) if matches!(err.kind(), ErrorKind::NoAcknowledge(_)) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Select the given channels of a TCA9548A/PCA9548 multiplexer.
+///
+/// ### Arguments
+///
+/// * `i2c` - I2C bus the multiplexer is on.
+/// * `mux_address` - Address of the multiplexer.
+/// * `channels` - Bit mask of channels to enable, bit `n` being channel `n`. `0` disables every channel.
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")), keep_self, idents(AsyncI2c(sync = "I2c"))),
+    async(feature = "async", keep_self)
+)]
+pub async fn select_mux_channels<I2C: AsyncI2c>(
+    i2c: &mut I2C,
+    mux_address: u8,
+    channels: u8,
+) -> Result<(), I2C::Error> {
+    i2c.write(mux_address, &[channels]).await
+}
+
+/// Scan every channel of a TCA9548A/PCA9548 multiplexer for BMP085/BMP180 devices at `address`.
+/// Every channel is disabled once the scan completes.
+///
+/// ### Arguments
+///
+/// * `i2c` - I2C bus the multiplexer is on.
+/// * `mux_address` - Address of the multiplexer.
+/// * `address` - Address of the BMP devices, `0x77` unless an address translator is used.
+///
+/// ### Returns
+///
+/// Bit mask of the channels a device was found on, bit `n` being channel `n`
+#[maybe_async_cfg::maybe(
+    sync(cfg(not(feature = "async")), keep_self, idents(AsyncI2c(sync = "I2c"))),
+    async(feature = "async", keep_self)
+)]
+pub async fn scan_mux<I2C: AsyncI2c>(
+    i2c: &mut I2C,
+    mux_address: u8,
+    address: u8,
+) -> Result<u8, I2C::Error> {
+    let mut found = 0;
+    for channel in 0..MUX_CHANNELS {
+        select_mux_channels(i2c, mux_address, 1 << channel).await?;
+        if probe(i2c, address).await? {
+            found |= 1 << channel;
+        }
+    }
+    select_mux_channels(i2c, mux_address, 0).await?;
+
+    Ok(found)
+}
@@ -0,0 +1,113 @@
+//! Test-only BMP085/BMP180 simulator.
+//!
+//! Produces the uncompensated readings (`UT`/`UP`) a device with the given calibration would report
+//! for some physical temperature and pressure, by solving the datasheet's compensation equations for the
+//! readings. It doesn't use the compensation code in [`logic`](crate::logic), so that round trips through
+//! it check that code.
+
+use crate::types::{CalibrationData, Oss};
+
+/// Largest value an uncompensated temperature reading can take (16 bits).
+const UT_MAX: i32 = 0xFFFF;
+
+/// Computes the uncompensated temperature reading for `temperature` (in ºC).
+///
+/// ### Returns
+///
+/// The `UT` value, or `None` if the temperature can't be represented with this calibration.
+pub fn uncompensated_temperature(calib_data: &CalibrationData, temperature: f32) -> Option<i32> {
+    let ac5 = calib_data.ac5 as f64;
+    let ac6 = calib_data.ac6 as f64;
+    let mc = calib_data.mc as f64;
+    let md = calib_data.md as f64;
+
+    // T = (B5 + 8) / 2^4 in 0.1 ºC
+    let b5 = temperature as f64 * 160.0;
+    // B5 = X1 + MC * 2^11 / (X1 + MD), i.e. X1^2 + (MD - B5) * X1 + MC * 2^11 - B5 * MD = 0
+    let discriminant = (md - b5) * (md - b5) - 4.0 * (mc * 2048.0 - b5 * md);
+    if discriminant < 0.0 {
+        return None;
+    }
+    // The compensation curve has a pole at `X1 == -MD`, only the root above it is physical
+    let x1 = (b5 - md + libm::sqrt(discriminant)) / 2.0;
+    if x1 + md <= 0.0 {
+        return None;
+    }
+    // X1 = (UT - AC6) * AC5 / 2^15
+    let ut = libm::round(x1 * 32_768.0 / ac5 + ac6);
+
+    (0.0..=UT_MAX as f64).contains(&ut).then_some(ut as i32)
+}
+
+/// Computes the uncompensated pressure reading for `pressure` (in Pa).
+///
+/// ### Arguments
+///
+/// * `oss` - Oversampling setting the reading is taken with.
+/// * `b5` - B5 coefficient from temperature calculation.
+/// * `pressure` - Target pressure.
+///
+/// ### Returns
+///
+/// The `UP` value compensating closest to `pressure`, or `None` if the pressure can't be represented
+/// with this calibration.
+pub fn uncompensated_pressure(
+    calib_data: &CalibrationData,
+    oss: Oss,
+    b5: i32,
+    pressure: i32,
+) -> Option<i32> {
+    let shift = oss.val();
+
+    // B3 and B4 don't depend on the reading, they are taken from the datasheet as is
+    let b6 = b5 as i64 - 4000;
+    let x3 =
+        ((calib_data.b2 as i64 * ((b6 * b6) >> 12)) >> 11) + ((calib_data.ac2 as i64 * b6) >> 11);
+    let b3 = (((calib_data.ac1 as i64 * 4 + x3) << shift) + 2) / 4;
+    let x3 = (((calib_data.ac3 as i64 * b6) >> 13)
+        + ((calib_data.b1 as i64 * ((b6 * b6) >> 12)) >> 16)
+        + 2)
+        >> 2;
+    let b4 = (calib_data.ac4 as i64 * (x3 + 32_768)) >> 15;
+    if b4 <= 0 {
+        return None;
+    }
+
+    // P = p + ((p / 2^8)^2 * 3038 / 2^16 - 7357 * p / 2^16 + 3791) / 2^4, a quadratic in p
+    let a = 3038.0 / 68_719_476_736.0;
+    let b = 1.0 - 7357.0 / 1_048_576.0;
+    let c = 3791.0 / 16.0 - pressure as f64;
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let p = (-b + libm::sqrt(discriminant)) / (2.0 * a);
+    // `p / 2^8` is truncated, so solve again with the truncated square term
+    let x1 = libm::floor(p / 256.0) * libm::floor(p / 256.0) * 3038.0 / 65_536.0;
+    let p = (pressure as f64 - (x1 + 3791.0) / 16.0) / b;
+
+    // p = B7 * 2 / B4, with B7 = (UP - B3) * (50000 >> oss)
+    let b7 = p * b4 as f64 / 2.0;
+    let up = libm::round(b7 / (50_000 >> shift) as f64) as i64 + b3;
+
+    // The output jumps by a few Pa where `p / 2^8` is truncated to the next value, so the reading just
+    // around the solution may compensate closer to `pressure`
+    (up - 2..=up + 2)
+        .filter(|&up| (0..=oss.max_uncompensated_pressure() as i64).contains(&up))
+        .min_by_key(|&up| (datasheet_pressure(b3, b4, shift, up) - pressure as i64).abs())
+        .map(|up| up as i32)
+}
+
+/// Last steps of the datasheet's pressure compensation, from B3 and B4 and the reading to the pressure in Pa.
+fn datasheet_pressure(b3: i64, b4: i64, shift: u8, up: i64) -> i64 {
+    let b7 = (up - b3) * (50_000 >> shift);
+    let p = if b7 < 0x8000_0000 {
+        b7 * 2 / b4
+    } else {
+        b7 / b4 * 2
+    };
+    let x1 = ((p >> 8) * (p >> 8) * 3038) >> 16;
+    let x2 = (-7357 * p) >> 16;
+
+    p + ((x1 + x2 + 3791) >> 4)
+}
@@ -0,0 +1,61 @@
+//! Periodic measurements as a `futures` stream, enabled with the `stream` feature.
+
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::I2c;
+use futures_util::stream::{self, Stream};
+
+use crate::types::{BMPError, Measurement, BMP};
+
+impl<I2C, D> BMP<I2C, D>
+where
+    I2C: I2c,
+    D: DelayNs,
+{
+    /// Measure temperature and pressure periodically.
+    ///
+    /// Measurements start at fixed deadlines, one period apart from the first one, which is taken right away.
+    /// A measurement is only taken when the stream is polled, so a slow consumer skips the deadlines that
+    /// passed in the meantime instead of accumulating readings: the next measurement is taken right away,
+    /// and the following ones keep to the original deadlines, without drifting behind them.
+    ///
+    /// ### Arguments
+    ///
+    /// * `period_us` - Time between the start of consecutive measurements, in µs.
+    /// * `now_us` - Monotonic clock in µs, e.g. `|| Instant::now().as_micros()` with `embassy-time`. The driver's
+    ///   delay can only wait, not tell the time, so the deadlines can't be kept without it.
+    ///
+    /// ### Returns
+    ///
+    /// Endless stream of [`Measurement`] results, as returned by [`read_measurement`](BMP::read_measurement)
+    pub fn stream<'a, F>(
+        &'a mut self,
+        period_us: u32,
+        now_us: F,
+    ) -> impl Stream<Item = Result<Measurement, BMPError<I2C::Error>>> + 'a
+    where
+        F: FnMut() -> u64 + 'a,
+    {
+        let period_us = period_us.max(1) as u64;
+        stream::unfold(
+            (self, now_us, None),
+            move |(bmp, mut now_us, deadline_us): (_, F, Option<u64>)| async move {
+                let now = now_us();
+                let deadline_us = match deadline_us {
+                    None => now,
+                    Some(deadline_us) if now < deadline_us => {
+                        // At most one period away, which fits in a u32
+                        bmp.delayer.delay_us((deadline_us - now) as u32).await;
+                        deadline_us
+                    }
+                    Some(deadline_us) => {
+                        // Skip the deadlines that passed, keeping to the same schedule
+                        let late_us = now - deadline_us;
+                        deadline_us + late_us - late_us % period_us
+                    }
+                };
+                let result = bmp.read_measurement().await;
+                Some((result, (bmp, now_us, Some(deadline_us + period_us))))
+            },
+        )
+    }
+}
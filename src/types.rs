@@ -1,31 +1,110 @@
 use crate::constants::{BMP_DEVICE_ADDR, DEFAULT_SEA_LEVEL_PESSURE};
-use core::fmt::{Display, Formatter};
+use core::fmt::{Debug, Display, Formatter};
+use core::marker::PhantomData;
 
 /// BMP085/BMP180 driver.
-pub struct BMP<I2C, D> {
+///
+/// Created in the [`Uninitialized`] state by [`BMP::new`](BMP::new), measurements are only available
+/// once [`BMP::init`](BMP::init) has read the device's calibration data.
+pub struct BMP<I2C, D, S = Initialized> {
     pub(crate) i2c: I2C,
     pub(crate) delayer: D,
     pub(crate) address: u8,
     pub(crate) calib_data: CalibrationData,
     pub(crate) oss: Oss,
     pub(crate) sea_level_pressure: i32,
+    pub(crate) timing: ConversionTiming,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) stats: TransactionStats,
+    pub(crate) limits: PlausibilityLimits,
+    pub(crate) stuck_threshold: Option<u16>,
+    pub(crate) compensation: CompensationAlgorithm,
+    pub(crate) clock: Option<fn() -> u32>,
+    pub(crate) health: Health,
+    pub(crate) ut_run: RepeatRun<u16>,
+    pub(crate) up_run: RepeatRun<u32>,
+    pub(crate) _state: PhantomData<S>,
+}
+
+/// [`BMP`] driver state before calibration data has been read.
+///
+/// Measurements can't be taken in this state:
+///
+/// ```compile_fail
+/// # use bmp085_180_rs::BMP;
+/// # use embedded_hal_mock::eh1::{delay::NoopDelay, i2c::Mock as I2cMock};
+/// let mut bmp = BMP::new(I2cMock::new(&[]), NoopDelay, Default::default());
+/// bmp.read_pressure();
+/// ```
+pub struct Uninitialized;
+
+/// [`BMP`] driver state once calibrated and ready to take measurements.
+pub struct Initialized;
+
+/// Driver operation during which an error occurred
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Reading the device identifier in [`test_connection`](BMP::test_connection)
+    Connect,
+    /// Reading the calibration data in [`init`](BMP::init)
+    Init,
+    /// Temperature conversion, which is also part of every pressure measurement
+    Temperature,
+    /// Pressure conversion
+    Pressure,
+    /// Soft reset in [`soft_reset`](BMP::soft_reset)
+    Reset,
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Operation::Connect => write!(f, "connection test"),
+            Operation::Init => write!(f, "initialization"),
+            Operation::Temperature => write!(f, "temperature measurement"),
+            Operation::Pressure => write!(f, "pressure measurement"),
+            Operation::Reset => write!(f, "soft reset"),
+        }
+    }
 }
 
 /// All possible errors in this crate
 #[derive(Debug, PartialEq, Eq)]
 pub enum BMPError<I2CErr> {
-    /// I2C bus error
-    I2C(I2CErr),
-    /// Invalid BMP device identifier
-    InvalidDeviceId,
-    /// Invalid calibration data; should re-calibrate device
-    InvalidCalibrationData,
+    /// I2C bus error while accessing a register
+    I2C {
+        /// Operation the register access was part of
+        operation: Operation,
+        /// Address of the register being read or written
+        register: u8,
+        /// Error reported by the I2C peripheral
+        source: I2CErr,
+    },
+    /// Invalid BMP device identifier, carrying the identifier read from the device
+    InvalidDeviceId(u8),
+    /// Conversion did not complete within the worst-case conversion time
+    ConversionTimeout(Operation),
+    /// Measurement could not be compensated, see [`CompensationError`]
+    Compensation(CompensationError),
+    /// Compensated reading is outside of the configured [`PlausibilityLimits`]
+    OutOfRange(Operation),
+    /// Device kept returning the same uncompensated reading, see [`Config::stuck_threshold`]
+    StuckSensor(Operation),
+    /// Driver [`Config`] is invalid, e.g. a [`stuck_threshold`](Config::stuck_threshold) below 2
+    InvalidConfig,
 }
 
-impl<E> From<E> for BMPError<E> {
-    /// Any unmapped error gets mapped to an I2C error.
-    fn from(err: E) -> Self {
-        BMPError::I2C(err)
+impl<E: embedded_hal::i2c::Error> BMPError<E> {
+    /// Get the generic `embedded-hal` kind of an I2C bus error.
+    ///
+    /// ### Returns
+    ///
+    /// The [`ErrorKind`](embedded_hal::i2c::ErrorKind) of the I2C error, `None` for any other error
+    pub fn kind(&self) -> Option<embedded_hal::i2c::ErrorKind> {
+        match self {
+            BMPError::I2C { source, .. } => Some(source.kind()),
+            _ => None,
+        }
     }
 }
 
@@ -33,9 +112,67 @@ impl<E> From<E> for BMPError<E> {
 impl<E: Display> Display for BMPError<E> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
-            BMPError::I2C(e) => write!(f, "I2C bus error: {e}"),
-            BMPError::InvalidDeviceId => write!(f, "Unrecognized BMP device identifier"),
-            BMPError::InvalidCalibrationData => {
+            BMPError::I2C {
+                operation,
+                register,
+                source,
+            } => write!(
+                f,
+                "I2C bus error during {operation}, register {register:#04X}: {source}"
+            ),
+            BMPError::InvalidDeviceId(id) => {
+                write!(f, "Unrecognized BMP device identifier {id:#04X}")
+            }
+            BMPError::ConversionTimeout(operation) => {
+                write!(f, "Conversion did not complete in time during {operation}")
+            }
+            BMPError::Compensation(e) => write!(f, "Compensation error: {e}"),
+            BMPError::OutOfRange(operation) => {
+                write!(f, "Implausible reading during {operation}")
+            }
+            BMPError::StuckSensor(operation) => {
+                write!(f, "Output registers stopped updating during {operation}")
+            }
+            BMPError::InvalidConfig => write!(f, "Invalid driver configuration"),
+        }
+    }
+}
+
+impl<E: Debug + Display> core::error::Error for BMPError<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            BMPError::Compensation(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Errors produced while compensating uncompensated readings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompensationError {
+    /// Uncompensated temperature reading is outside of its 16-bit range
+    TemperatureReadingOutOfRange,
+    /// Uncompensated pressure reading is outside of the range of the oversampling setting
+    PressureReadingOutOfRange,
+    /// A divisor in the compensation formulas evaluated to zero
+    DivisionByZero,
+    /// Calibration coefficients are invalid, or inconsistent with the readings
+    InvalidCalibration,
+}
+
+impl Display for CompensationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CompensationError::TemperatureReadingOutOfRange => {
+                write!(f, "Uncompensated temperature reading out of range")
+            }
+            CompensationError::PressureReadingOutOfRange => {
+                write!(f, "Uncompensated pressure reading out of range")
+            }
+            CompensationError::DivisionByZero => {
+                write!(f, "Division by zero while compensating reading")
+            }
+            CompensationError::InvalidCalibration => {
                 write!(
                     f,
                     "Invalid calibration values were present; device re-calibration recommended"
@@ -45,6 +182,9 @@ impl<E: Display> Display for BMPError<E> {
     }
 }
 
+impl core::error::Error for CompensationError {}
+
+/// Calibration coefficients stored in the device's EEPROM, read during driver initialization.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CalibrationData {
     pub ac1: i16,
@@ -60,6 +200,115 @@ pub struct CalibrationData {
     pub md: i16,
 }
 
+impl CalibrationData {
+    /// Parses the coefficients from the contents of the calibration registers, `0xAA` to `0xBF`.
+    pub(crate) fn from_be_bytes(bytes: &[u8; 22]) -> Self {
+        let word = |i: usize| u16::from_be_bytes([bytes[2 * i], bytes[2 * i + 1]]);
+        CalibrationData {
+            ac1: word(0) as i16,
+            ac2: word(1) as i16,
+            ac3: word(2) as i16,
+            ac4: word(3),
+            ac5: word(4),
+            ac6: word(5),
+            b1: word(6) as i16,
+            b2: word(7) as i16,
+            mb: word(8) as i16,
+            mc: word(9) as i16,
+            md: word(10) as i16,
+        }
+    }
+
+    /// Serializes the coefficients as the contents of the calibration registers, `0xAA` to `0xBF`.
+    pub(crate) fn to_be_bytes(self) -> [u8; 22] {
+        let words = [
+            self.ac1 as u16,
+            self.ac2 as u16,
+            self.ac3 as u16,
+            self.ac4,
+            self.ac5,
+            self.ac6,
+            self.b1 as u16,
+            self.b2 as u16,
+            self.mb as u16,
+            self.mc as u16,
+            self.md as u16,
+        ];
+        let mut bytes = [0; 22];
+        for (chunk, word) in bytes.chunks_exact_mut(2).zip(words) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Checks the coefficients as described by the datasheet: no value may be `0x0000` or `0xFFFF`,
+    /// which is what a missing or faulty EEPROM reads as.
+    pub fn is_valid(&self) -> bool {
+        [
+            self.ac1 as u16,
+            self.ac2 as u16,
+            self.ac3 as u16,
+            self.ac4,
+            self.ac5,
+            self.ac6,
+            self.b1 as u16,
+            self.b2 as u16,
+            self.mb as u16,
+            self.mc as u16,
+            self.md as u16,
+        ]
+        .iter()
+        .all(|&coefficient| coefficient != 0x0000 && coefficient != 0xFFFF)
+    }
+}
+
+/// Uncompensated readings taken from the device, which can be compensated later on with [`compensate`](crate::compensate).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RawSample {
+    /// Uncompensated temperature (UT)
+    pub ut: u16,
+    /// Uncompensated pressure (UP), 16 to 19 bits depending on `oss`
+    pub up: u32,
+    /// Oversampling setting the pressure was measured with
+    pub oss: Oss,
+}
+
+/// Compensated temperature and pressure measurement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    /// Temperature in degrees Celsius (ºC)
+    pub temperature: f32,
+    /// Pressure in pascals (Pa)
+    pub pressure: i32,
+}
+
+/// Results of [`BMP::self_test`](BMP::self_test), suitable for a go/no-go test of a board.
+#[derive(Debug, PartialEq)]
+pub struct SelfTestReport<I2CErr> {
+    /// Result of checking the device identifier
+    pub connection: Result<(), BMPError<I2CErr>>,
+    /// Whether the calibration data read during initialization is valid, see [`CalibrationData::is_valid`]
+    pub calibration: bool,
+    /// Measurement taken with each oversampling setting, from [`LowPower`](Oss::LowPower) to [`UltraHighRes`](Oss::UltraHighRes).
+    /// Readings are checked against the driver's [`PlausibilityLimits`].
+    pub measurements: [Result<Measurement, BMPError<I2CErr>>; 4],
+    /// Whether the successful measurements agree with each other across oversampling settings
+    pub consistent: bool,
+    /// Result of soft resetting the device and checking its identifier afterwards
+    pub soft_reset: Result<(), BMPError<I2CErr>>,
+}
+
+impl<E> SelfTestReport<E> {
+    /// Checks whether every part of the self-test succeeded.
+    pub fn passed(&self) -> bool {
+        self.connection.is_ok()
+            && self.calibration
+            && self.measurements.iter().all(Result::is_ok)
+            && self.consistent
+            && self.soft_reset.is_ok()
+    }
+}
+
 /// Used to configure the driver's oversampling setting. The higher the value, the more measurements are taken and more accurate the results are,
 /// although the measurement will take longer. Only applies to pressure measurements.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -83,6 +332,238 @@ impl Oss {
             Oss::UltraHighRes => 3,
         }
     }
+
+    /// Largest uncompensated pressure reading for this setting, which has `16 + oss` bits.
+    pub(crate) fn max_uncompensated_pressure(&self) -> i32 {
+        (1 << (16 + self.val())) - 1
+    }
+}
+
+/// Time to wait for conversions to complete before reading their results.
+///
+/// Defaults to the maximum conversion times from the datasheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConversionTiming {
+    /// Temperature conversion time in µs.
+    pub temperature_us: u32,
+    /// Pressure conversion time in µs for each oversampling setting, from [`LowPower`](Oss::LowPower) to [`UltraHighRes`](Oss::UltraHighRes).
+    pub pressure_us: [u32; 4],
+    /// If set, after waiting, poll the SCO (start of conversion) bit of the control register every given µs
+    /// until the conversion completes. Polling gives up with [`ConversionTimeout`](BMPError::ConversionTimeout)
+    /// once the worst-case conversion time has passed, which is the longest of the configured and datasheet maximum times.
+    pub sco_poll_interval_us: Option<u32>,
+}
+
+impl ConversionTiming {
+    /// Maximum conversion times from the datasheet.
+    pub const DATASHEET_MAX: ConversionTiming = ConversionTiming {
+        temperature_us: 4500,
+        pressure_us: [4500, 7500, 13_500, 25_500],
+        sco_poll_interval_us: None,
+    };
+
+    /// Typical conversion times from the datasheet, polling the SCO bit for conversions that take longer.
+    pub const FAST: ConversionTiming = ConversionTiming {
+        temperature_us: 3000,
+        pressure_us: [3000, 5000, 9000, 17_000],
+        sco_poll_interval_us: Some(500),
+    };
+
+    /// Don't wait before reading the control register, poll the SCO bit every `interval_us` from the start
+    /// of the conversion instead. Gives the lowest latency at the cost of extra bus traffic.
+    pub const fn polling(interval_us: u32) -> ConversionTiming {
+        ConversionTiming {
+            temperature_us: 0,
+            pressure_us: [0; 4],
+            sco_poll_interval_us: Some(interval_us),
+        }
+    }
+
+    /// Pressure conversion time in µs for the given oversampling setting.
+    pub fn pressure_us(&self, oss: Oss) -> u32 {
+        self.pressure_us[oss.val() as usize]
+    }
+}
+
+impl Default for ConversionTiming {
+    fn default() -> Self {
+        ConversionTiming::DATASHEET_MAX
+    }
+}
+
+/// Policy for retrying register accesses that fail with an I2C bus error, such as a NACK caused by a glitch.
+///
+/// Defaults to a single attempt, i.e. no retries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    /// Attempts made for each register access before giving up, including the first one.
+    pub max_attempts: u8,
+    /// Wait before the first retry in µs, doubling with each subsequent retry.
+    pub backoff_us: u32,
+}
+
+impl RetryPolicy {
+    /// Wait in µs after the given failed attempt.
+    pub(crate) fn backoff_us(&self, attempt: u8) -> u32 {
+        self.backoff_us
+            .saturating_mul(1 << (attempt.saturating_sub(1)).min(31))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            backoff_us: 0,
+        }
+    }
+}
+
+/// Counters of register accesses affected by I2C bus errors, see [`RetryPolicy`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransactionStats {
+    /// Register accesses that succeeded after one or more retries.
+    pub retried: u32,
+    /// Register accesses that failed on every attempt.
+    pub failed: u32,
+}
+
+/// Range of plausible compensated readings. Readings outside of it are most likely caused by a faulty
+/// device or bus, and are reported as [`OutOfRange`](BMPError::OutOfRange).
+///
+/// Defaults to the operating range from the datasheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlausibilityLimits {
+    /// Lowest plausible pressure in Pa.
+    pub min_pressure: i32,
+    /// Highest plausible pressure in Pa.
+    pub max_pressure: i32,
+    /// Lowest plausible temperature in 0.1 ºC.
+    pub min_temperature: i32,
+    /// Highest plausible temperature in 0.1 ºC.
+    pub max_temperature: i32,
+}
+
+impl PlausibilityLimits {
+    /// Operating range from the datasheet: 300 to 1100 hPa, and -40 to +85 ºC.
+    pub const DATASHEET: PlausibilityLimits = PlausibilityLimits {
+        min_pressure: 30_000,
+        max_pressure: 110_000,
+        min_temperature: -400,
+        max_temperature: 850,
+    };
+
+    /// No limits, every compensated reading is accepted.
+    pub const NONE: PlausibilityLimits = PlausibilityLimits {
+        min_pressure: i32::MIN,
+        max_pressure: i32::MAX,
+        min_temperature: i32::MIN,
+        max_temperature: i32::MAX,
+    };
+
+    /// Checks whether `temperature` (in ºC) is within the limits.
+    pub fn contains_temperature(&self, temperature: f32) -> bool {
+        temperature >= self.min_temperature as f32 / 10.0
+            && temperature <= self.max_temperature as f32 / 10.0
+    }
+
+    /// Checks whether `pressure` (in Pa) is within the limits.
+    pub fn contains_pressure(&self, pressure: i32) -> bool {
+        (self.min_pressure..=self.max_pressure).contains(&pressure)
+    }
+
+    /// Checks whether both readings of `measurement` are within the limits.
+    pub fn contains(&self, measurement: &Measurement) -> bool {
+        self.contains_temperature(measurement.temperature)
+            && self.contains_pressure(measurement.pressure)
+    }
+}
+
+impl Default for PlausibilityLimits {
+    fn default() -> Self {
+        PlausibilityLimits::DATASHEET
+    }
+}
+
+/// Summary of the device's health, as seen by the driver's measurements.
+///
+/// Successful measurements are timestamped with the clock set by [`BMP::set_clock`], so that
+/// [`since_last_success`](Health::since_last_success) tells how long the device has been failing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Health {
+    /// Measurements attempted since the driver was initialized.
+    pub measurements: u32,
+    /// Time of the last successful measurement in milliseconds (ms), read from the clock set by
+    /// [`BMP::set_clock`]. `None` if none succeeded yet, or if no clock is set.
+    pub last_success_ms: Option<u32>,
+    /// Measurements that failed since the last successful one.
+    pub consecutive_failures: u32,
+    /// Whether the device's output registers appear to have stopped updating,
+    /// see [`Config::stuck_threshold`].
+    pub stuck: bool,
+}
+
+impl Health {
+    /// Get the time elapsed since the last successful measurement.
+    ///
+    /// ### Arguments
+    ///
+    /// * `now_ms` - Current time in milliseconds (ms), from the same clock. Wrapping around is supported.
+    ///
+    /// ### Returns
+    ///
+    /// Elapsed time in milliseconds (ms), `None` if no measurement was timestamped yet
+    pub fn since_last_success(&self, now_ms: u32) -> Option<u32> {
+        self.last_success_ms
+            .map(|last_success_ms| now_ms.wrapping_sub(last_success_ms))
+    }
+
+    pub(crate) fn record<T, E>(&mut self, result: &Result<T, E>, now_ms: Option<u32>) {
+        self.measurements = self.measurements.saturating_add(1);
+        match result {
+            Ok(_) => {
+                if now_ms.is_some() {
+                    self.last_success_ms = now_ms;
+                }
+                self.consecutive_failures = 0;
+            }
+            Err(_) => self.consecutive_failures = self.consecutive_failures.saturating_add(1),
+        }
+    }
+}
+
+/// Tracks how many times in a row the same value has been seen.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct RepeatRun<T> {
+    last: Option<T>,
+    count: u16,
+}
+
+impl<T: PartialEq> RepeatRun<T> {
+    /// Records `value`, returning how many times in a row it has been seen.
+    pub(crate) fn push(&mut self, value: T) -> u16 {
+        if self.last.as_ref() == Some(&value) {
+            self.count = self.count.saturating_add(1);
+        } else {
+            self.last = Some(value);
+            self.count = 1;
+        }
+        self.count
+    }
+
+    pub(crate) fn count(&self) -> u16 {
+        self.count
+    }
+}
+
+/// Algorithm compensating the uncompensated readings of the device.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompensationAlgorithm {
+    /// Integer algorithm of the datasheet, see [`compensate`](crate::compensate).
+    #[default]
+    Datasheet,
+    /// Floating-point polynomial algorithm, with smoother output, see [`compensate_float`](crate::logic::compensate_float).
+    FloatingPoint,
 }
 
 /// Driver configuration, used only during driver initialization.
@@ -95,6 +576,20 @@ pub struct Config {
     /// This value can change slightly under atmospheric conditions, so you can fine-tune it here.
     /// Default is `101_325`.
     pub sea_level_pressure: i32,
+    /// Conversion wait times, default is [`DATASHEET_MAX`](ConversionTiming::DATASHEET_MAX).
+    /// Boards with slow buses may need longer times, while [`FAST`](ConversionTiming::FAST) reduces latency.
+    pub timing: ConversionTiming,
+    /// Retry policy for register accesses, default is no retries.
+    pub retry: RetryPolicy,
+    /// Range of plausible readings, default is [`DATASHEET`](PlausibilityLimits::DATASHEET).
+    pub limits: PlausibilityLimits,
+    /// If set, measurements fail with [`StuckSensor`](BMPError::StuckSensor) once the device returns the same
+    /// uncompensated temperature or pressure this many times in a row. Temperature readings can legitimately
+    /// repeat in a stable environment, so the threshold should be generous. It must be at least 2, or
+    /// [`init`](BMP::init) fails with [`InvalidConfig`](BMPError::InvalidConfig). Default is `None`.
+    pub stuck_threshold: Option<u16>,
+    /// Algorithm compensating the readings, default is [`Datasheet`](CompensationAlgorithm::Datasheet).
+    pub compensation: CompensationAlgorithm,
 }
 
 impl Default for Config {
@@ -105,6 +600,11 @@ impl Default for Config {
             oss: Oss::LowPower,
             address: BMP_DEVICE_ADDR,
             sea_level_pressure: DEFAULT_SEA_LEVEL_PESSURE,
+            timing: ConversionTiming::default(),
+            retry: RetryPolicy::default(),
+            limits: PlausibilityLimits::default(),
+            stuck_threshold: None,
+            compensation: CompensationAlgorithm::Datasheet,
         }
     }
 }
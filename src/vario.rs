@@ -0,0 +1,274 @@
+//! Variometer computing the vertical speed from a history of altitudes.
+//!
+//! The vertical speed is the slope of a least-squares linear regression over the altitudes of the last
+//! [`window_ms`](VarioConfig::window_ms), which smooths the noise of single readings:
+//!
+//! ```ignore
+//! let mut vario = Variometer::<32>::new(VarioConfig::DEFAULT);
+//! loop {
+//!     vario.push_pressure(now_ms(), bmp.read_pressure()?, sea_level_pressure);
+//!     match vario.tone() {
+//!         Some(VarioTone::Climb) => beep(vario.vertical_speed()),
+//!         Some(VarioTone::Sink) => alarm(),
+//!         _ => {}
+//!     }
+//! }
+//! ```
+
+use crate::logic;
+
+/// Window and audio thresholds of a [`Variometer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VarioConfig {
+    /// Duration of the altitude history the vertical speed is calculated from, in milliseconds (ms).
+    pub window_ms: u32,
+    /// Vertical speed above which the variometer signals a climb, in m/s.
+    pub climb_threshold: f32,
+    /// Vertical speed below which the variometer signals a sink, in m/s (negative).
+    pub sink_threshold: f32,
+}
+
+impl VarioConfig {
+    /// Two seconds of history, signaling climbs above 0.2 m/s and sinks below -2 m/s,
+    /// as usual for paragliders.
+    pub const DEFAULT: VarioConfig = VarioConfig {
+        window_ms: 2000,
+        climb_threshold: 0.2,
+        sink_threshold: -2.0,
+    };
+}
+
+impl Default for VarioConfig {
+    fn default() -> Self {
+        VarioConfig::DEFAULT
+    }
+}
+
+/// Audio signal of a variometer, depending on the vertical speed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VarioTone {
+    /// Climbing faster than the climb threshold.
+    Climb,
+    /// Between the sink and climb thresholds, usually silent.
+    Neutral,
+    /// Sinking faster than the sink threshold.
+    Sink,
+}
+
+/// Variometer keeping the last `N` altitudes with their timestamps.
+///
+/// `N` must be large enough to hold every reading of a [`window_ms`](VarioConfig::window_ms),
+/// as the oldest readings are dropped once full.
+#[derive(Debug, Clone)]
+pub struct Variometer<const N: usize> {
+    config: VarioConfig,
+    samples: [(u32, f32); N],
+    /// Index of the oldest sample.
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> Variometer<N> {
+    /// Creates a new variometer, with an empty history.
+    ///
+    /// ### Arguments
+    ///
+    /// * `config` - [`Window and thresholds`](VarioConfig) of the variometer.
+    pub fn new(config: VarioConfig) -> Self {
+        assert!(N >= 2);
+        Variometer {
+            config,
+            samples: [(0, 0.0); N],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Adds an altitude to the history.
+    ///
+    /// ### Arguments
+    ///
+    /// * `timestamp_ms` - Time of the reading in milliseconds (ms), from any monotonic clock.
+    ///   Wrapping around is supported.
+    /// * `altitude` - Altitude in meters (m), e.g. from [`BMP::read_altitude`](crate::BMP::read_altitude).
+    pub fn push(&mut self, timestamp_ms: u32, altitude: f32) {
+        if self.len == N {
+            self.samples[self.start] = (timestamp_ms, altitude);
+            self.start = (self.start + 1) % N;
+        } else {
+            self.samples[(self.start + self.len) % N] = (timestamp_ms, altitude);
+            self.len += 1;
+        }
+    }
+
+    /// Adds a pressure to the history, converted to altitude as by [`BMP::read_altitude`](crate::BMP::read_altitude).
+    ///
+    /// ### Arguments
+    ///
+    /// * `timestamp_ms` - Time of the reading in milliseconds (ms), from any monotonic clock.
+    /// * `pressure` - Pressure in pascals (Pa), e.g. from [`BMP::read_pressure`](crate::BMP::read_pressure).
+    /// * `sea_level_pressure` - Pressure at sea level in pascals (Pa).
+    pub fn push_pressure(&mut self, timestamp_ms: u32, pressure: i32, sea_level_pressure: i32) {
+        self.push(
+            timestamp_ms,
+            logic::calculate_altitude(pressure, sea_level_pressure),
+        );
+    }
+
+    /// Calculate the vertical speed over the window.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// Vertical speed in meters per second (m/s), positive when climbing. `None` until the window holds
+    /// readings taken at two different times.
+    pub fn vertical_speed(&self) -> Option<f32> {
+        let newest = self.samples[(self.start + self.len + N - 1) % N].0;
+        // Times relative to the newest reading, in s, keep their precision in f32
+        let window = (0..self.len)
+            .map(|i| self.samples[(self.start + i) % N])
+            .map(|(timestamp, altitude)| (newest.wrapping_sub(timestamp), altitude))
+            .filter(|&(age, _)| age <= self.config.window_ms)
+            .map(|(age, altitude)| (-(age as f32) / 1000.0, altitude));
+
+        let (mut n, mut sum_t, mut sum_h) = (0.0, 0.0, 0.0);
+        for (t, h) in window.clone() {
+            n += 1.0;
+            sum_t += t;
+            sum_h += h;
+        }
+        if n < 2.0 {
+            return None;
+        }
+        let (mean_t, mean_h) = (sum_t / n, sum_h / n);
+        let (mut covariance, mut variance) = (0.0, 0.0);
+        for (t, h) in window {
+            covariance += (t - mean_t) * (h - mean_h);
+            variance += (t - mean_t) * (t - mean_t);
+        }
+
+        (variance > 0.0).then(|| covariance / variance)
+    }
+
+    /// Get the audio signal for the current vertical speed.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// [`VarioTone`] according to the configured thresholds, `None` while the vertical speed is unknown
+    pub fn tone(&self) -> Option<VarioTone> {
+        self.vertical_speed().map(|speed| {
+            if speed > self.config.climb_threshold {
+                VarioTone::Climb
+            } else if speed < self.config.sink_threshold {
+                VarioTone::Sink
+            } else {
+                VarioTone::Neutral
+            }
+        })
+    }
+
+    /// Forget every reading of the history.
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEA_LEVEL_PRESSURE: i32 = 101_325;
+
+    /// Pressure in Pa at `altitude`, rounded like the device's readings.
+    fn pressure_at(altitude: f32) -> i32 {
+        libm::roundf(logic::pressure_at_altitude(altitude, SEA_LEVEL_PRESSURE)) as i32
+    }
+
+    /// Feeds a pressure ramp from `start` m at `vertical_speed`, with a reading every `period_ms`.
+    fn feed_ramp<const N: usize>(
+        vario: &mut Variometer<N>,
+        start: f32,
+        vertical_speed: f32,
+        period_ms: u32,
+        readings: u32,
+    ) {
+        for i in 0..readings {
+            let altitude = start + vertical_speed * (i * period_ms) as f32 / 1000.0;
+            vario.push_pressure(i * period_ms, pressure_at(altitude), SEA_LEVEL_PRESSURE);
+        }
+    }
+
+    #[test]
+    fn vertical_speed_is_unknown_without_two_readings() {
+        let mut vario = Variometer::<8>::new(VarioConfig::DEFAULT);
+
+        assert_eq!(vario.vertical_speed(), None);
+        vario.push(0, 100.0);
+        assert_eq!(vario.vertical_speed(), None);
+        assert_eq!(vario.tone(), None);
+        vario.push(100, 101.0);
+        assert!((vario.vertical_speed().unwrap() - 10.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn calculates_climb_from_pressure_ramp() {
+        let mut vario = Variometer::<32>::new(VarioConfig::DEFAULT);
+        feed_ramp(&mut vario, 500.0, 1.5, 100, 50);
+
+        assert!((vario.vertical_speed().unwrap() - 1.5).abs() < 0.1);
+        assert_eq!(vario.tone(), Some(VarioTone::Climb));
+    }
+
+    #[test]
+    fn calculates_sink_from_pressure_ramp() {
+        let mut vario = Variometer::<32>::new(VarioConfig::DEFAULT);
+        feed_ramp(&mut vario, 1500.0, -3.0, 100, 50);
+
+        assert!((vario.vertical_speed().unwrap() + 3.0).abs() < 0.1);
+        assert_eq!(vario.tone(), Some(VarioTone::Sink));
+    }
+
+    #[test]
+    fn level_flight_is_neutral() {
+        let mut vario = Variometer::<32>::new(VarioConfig::DEFAULT);
+        feed_ramp(&mut vario, 800.0, 0.0, 100, 50);
+
+        assert!(vario.vertical_speed().unwrap().abs() < 1e-3);
+        assert_eq!(vario.tone(), Some(VarioTone::Neutral));
+    }
+
+    #[test]
+    fn only_readings_in_window_are_used() {
+        let mut vario = Variometer::<64>::new(VarioConfig {
+            window_ms: 1000,
+            ..VarioConfig::DEFAULT
+        });
+        // Climbing for 3 s, then level for the last second
+        for i in 0..=40u32 {
+            let altitude = 100.0 + 2.0 * (i.min(30) as f32) / 10.0;
+            vario.push(i * 100, altitude);
+        }
+
+        assert!(vario.vertical_speed().unwrap().abs() < 1e-3);
+    }
+
+    #[test]
+    fn timestamps_wrap_around() {
+        let mut vario = Variometer::<8>::new(VarioConfig::DEFAULT);
+        vario.push(u32::MAX - 99, 100.0);
+        vario.push(0, 100.5);
+        vario.push(100, 101.0);
+
+        assert!((vario.vertical_speed().unwrap() - 5.0).abs() < 1e-3);
+        vario.clear();
+        assert_eq!(vario.vertical_speed(), None);
+    }
+}
@@ -0,0 +1,542 @@
+//! Weather helpers working on a history of pressure readings.
+//!
+//! A [`PressureHistory`] keeps timestamped station pressures, from which the 3-hour [`Tendency`] is
+//! derived as reported by weather stations. Together with the pressure reduced to sea level, e.g. with
+//! [`qnh`] or [`qff`], it gives a Zambretti [`Forecast`]:
+//!
+//! ```ignore
+//! let mut history = PressureHistory::<32>::new();
+//! loop {
+//!     let measurement = bmp.read_measurement()?;
+//!     history.push(now_s(), measurement.pressure);
+//!     if let Some(tendency) = history.tendency() {
+//!         let sea_level = qff(measurement.pressure, STATION_ALTITUDE, measurement.temperature);
+//!         let forecast = forecast(sea_level, tendency.trend);
+//!     }
+//!     sleep_minutes(10);
+//! }
+//! ```
+
+use core::fmt::{Display, Formatter};
+
+use crate::constants::{DRY_AIR_GAS_CONSTANT, LAPSE_RATE, STANDARD_GRAVITY, ZERO_CELSIUS};
+use crate::logic::pressure_ratio_at_altitude;
+
+/// Period over which the pressure tendency is reported, in seconds (s).
+pub const TENDENCY_PERIOD_S: u32 = 3 * 3600;
+
+/// Largest difference between the age of a reading and the age it stands in for, in seconds (s).
+const TENDENCY_TOLERANCE_S: u32 = 15 * 60;
+
+/// Largest change in Pa considered steady within half a tendency period, i.e. the 0.1 hPa resolution
+/// of weather reports.
+const STEADY_HALF_CHANGE: i32 = 10;
+
+/// Largest change in Pa over a tendency period considered steady by the Zambretti forecaster.
+const STEADY_TREND_CHANGE: i32 = 160;
+
+/// Whether the pressure rises, falls or remains steady.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Trend {
+    /// Rising by more than 1.6 hPa in 3 hours.
+    Rising,
+    /// Changing by at most 1.6 hPa in 3 hours.
+    Steady,
+    /// Falling by more than 1.6 hPa in 3 hours.
+    Falling,
+}
+
+impl Trend {
+    /// Get the trend of a pressure change over 3 hours, in pascals (Pa).
+    pub fn from_change(change: i32) -> Self {
+        if change > STEADY_TREND_CHANGE {
+            Trend::Rising
+        } else if change < -STEADY_TREND_CHANGE {
+            Trend::Falling
+        } else {
+            Trend::Steady
+        }
+    }
+}
+
+/// Characteristic of the pressure tendency over the last 3 hours, as coded in WMO code table 0200.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Characteristic {
+    /// Increasing, then decreasing; pressure the same or higher than 3 hours ago.
+    IncreasingThenDecreasing = 0,
+    /// Increasing, then steady; or increasing, then increasing more slowly.
+    IncreasingThenSteady = 1,
+    /// Increasing steadily or unsteadily.
+    Increasing = 2,
+    /// Decreasing or steady, then increasing; or increasing, then increasing more rapidly.
+    SteadyThenIncreasing = 3,
+    /// Steady; pressure the same as 3 hours ago.
+    Steady = 4,
+    /// Decreasing, then increasing; pressure the same or lower than 3 hours ago.
+    DecreasingThenIncreasing = 5,
+    /// Decreasing, then steady; or decreasing, then decreasing more slowly.
+    DecreasingThenSteady = 6,
+    /// Decreasing steadily or unsteadily.
+    Decreasing = 7,
+    /// Steady or increasing, then decreasing; or decreasing, then decreasing more rapidly.
+    SteadyThenDecreasing = 8,
+}
+
+impl Characteristic {
+    /// Classify the changes in Pa over the first and second halves of a tendency period.
+    fn from_changes(first: i32, second: i32) -> Self {
+        let steady = |change: i32| change.abs() <= STEADY_HALF_CHANGE;
+        let net = first + second;
+        if steady(net) {
+            return if first > STEADY_HALF_CHANGE && second < -STEADY_HALF_CHANGE {
+                Characteristic::IncreasingThenDecreasing
+            } else if first < -STEADY_HALF_CHANGE && second > STEADY_HALF_CHANGE {
+                Characteristic::DecreasingThenIncreasing
+            } else {
+                Characteristic::Steady
+            };
+        }
+
+        if net > 0 {
+            match (first, second) {
+                (_, s) if s < -STEADY_HALF_CHANGE => Characteristic::IncreasingThenDecreasing,
+                (f, _) if f <= STEADY_HALF_CHANGE => Characteristic::SteadyThenIncreasing,
+                (f, s) if steady(s) || s < f / 2 => Characteristic::IncreasingThenSteady,
+                (f, s) if s > f * 2 => Characteristic::SteadyThenIncreasing,
+                _ => Characteristic::Increasing,
+            }
+        } else {
+            match (first, second) {
+                (_, s) if s > STEADY_HALF_CHANGE => Characteristic::DecreasingThenIncreasing,
+                (f, _) if f >= -STEADY_HALF_CHANGE => Characteristic::SteadyThenDecreasing,
+                (f, s) if steady(s) || s > f / 2 => Characteristic::DecreasingThenSteady,
+                (f, s) if s < f * 2 => Characteristic::SteadyThenDecreasing,
+                _ => Characteristic::Decreasing,
+            }
+        }
+    }
+
+    /// Get the WMO code of the characteristic, from `0` to `8`.
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Pressure tendency over the last 3 hours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tendency {
+    /// Pressure change in pascals (Pa).
+    pub change: i32,
+    /// How the pressure changed.
+    pub characteristic: Characteristic,
+    /// Whether the pressure rises, falls or remains steady.
+    pub trend: Trend,
+}
+
+/// History of the last `N` station pressures with their timestamps.
+///
+/// `N` must be large enough to hold 3 hours of readings, as the oldest readings are dropped once full.
+#[derive(Debug, Clone)]
+pub struct PressureHistory<const N: usize> {
+    samples: [(u32, i32); N],
+    /// Index of the oldest sample.
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> PressureHistory<N> {
+    /// Creates a new, empty history.
+    pub fn new() -> Self {
+        assert!(N >= 3);
+        PressureHistory {
+            samples: [(0, 0); N],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Adds a pressure to the history.
+    ///
+    /// ### Arguments
+    ///
+    /// * `timestamp_s` - Time of the reading in seconds (s), from any monotonic clock.
+    ///   Wrapping around is supported.
+    /// * `pressure` - Station pressure in pascals (Pa), e.g. from [`BMP::read_pressure`](crate::BMP::read_pressure).
+    pub fn push(&mut self, timestamp_s: u32, pressure: i32) {
+        if self.len == N {
+            self.samples[self.start] = (timestamp_s, pressure);
+            self.start = (self.start + 1) % N;
+        } else {
+            self.samples[(self.start + self.len) % N] = (timestamp_s, pressure);
+            self.len += 1;
+        }
+    }
+
+    /// Iterate over the readings, from the oldest to the newest one.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, i32)> + '_ {
+        (0..self.len).map(|i| self.samples[(self.start + i) % N])
+    }
+
+    /// Get the reading whose age is closest to `age_s`, if close enough to stand in for it.
+    fn reading_aged(&self, age_s: u32) -> Option<i32> {
+        let (newest, _) = self.iter().last()?;
+        let (offset, pressure) = self
+            .iter()
+            .map(|(timestamp, pressure)| (newest.wrapping_sub(timestamp).abs_diff(age_s), pressure))
+            .min_by_key(|&(offset, _)| offset)?;
+        (offset <= TENDENCY_TOLERANCE_S).then_some(pressure)
+    }
+
+    /// Calculate the pressure tendency over the last 3 hours.
+    ///
+    /// ### Arguments
+    ///
+    /// None
+    ///
+    /// ### Returns
+    ///
+    /// The [`Tendency`], or `None` until the history holds readings from about 3 hours and 1.5 hours ago
+    pub fn tendency(&self) -> Option<Tendency> {
+        let start = self.reading_aged(TENDENCY_PERIOD_S)?;
+        let middle = self.reading_aged(TENDENCY_PERIOD_S / 2)?;
+        let end = self.reading_aged(0)?;
+        let change = end - start;
+
+        Some(Tendency {
+            change,
+            characteristic: Characteristic::from_changes(middle - start, end - middle),
+            trend: Trend::from_change(change),
+        })
+    }
+
+    /// Forget every reading of the history.
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Default for PressureHistory<N> {
+    fn default() -> Self {
+        PressureHistory::new()
+    }
+}
+
+/// Reduce station pressure to sea level in the standard atmosphere (QNH), as used for altimeter settings.
+///
+/// ### Arguments
+///
+/// * `pressure` - Station pressure in pascals (Pa).
+/// * `altitude` - Station altitude in meters (m), below the 44 330 m at which the standard atmosphere
+///   reaches zero pressure.
+///
+/// ### Returns
+///
+/// Sea level pressure in pascals (Pa), which [`BMP::read_altitude`](crate::BMP::read_altitude) turns back
+/// into the station altitude
+pub fn qnh(pressure: i32, altitude: f32) -> i32 {
+    libm::roundf(pressure as f32 / pressure_ratio_at_altitude(altitude)) as i32
+}
+
+/// Reduce station pressure to sea level using the actual temperature (QFF), as used for weather maps.
+///
+/// ### Arguments
+///
+/// * `pressure` - Station pressure in pascals (Pa).
+/// * `altitude` - Station altitude in meters (m).
+/// * `temperature` - Station temperature in degrees Celsius (ºC).
+///
+/// ### Returns
+///
+/// Sea level pressure in pascals (Pa)
+pub fn qff(pressure: i32, altitude: f32, temperature: f32) -> i32 {
+    // Mean temperature of the air column between the station and sea level
+    let mean_temperature = temperature + ZERO_CELSIUS + LAPSE_RATE * altitude / 2.0;
+    let exponent = STANDARD_GRAVITY * altitude / (DRY_AIR_GAS_CONSTANT * mean_temperature);
+    libm::roundf(pressure as f32 * libm::expf(exponent)) as i32
+}
+
+/// Zambretti forecast, from letter `A` (settled fine) to `Z` (stormy, much rain).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Forecast {
+    /// A: Settled fine.
+    SettledFine,
+    /// B: Fine weather.
+    Fine,
+    /// C: Becoming fine.
+    BecomingFine,
+    /// D: Fine, becoming less settled.
+    FineBecomingLessSettled,
+    /// E: Fine, possible showers.
+    FinePossibleShowers,
+    /// F: Fairly fine, improving.
+    FairlyFineImproving,
+    /// G: Fairly fine, possible showers early.
+    FairlyFinePossibleShowersEarly,
+    /// H: Fairly fine, showery later.
+    FairlyFineShoweryLater,
+    /// I: Showery early, improving.
+    ShoweryEarlyImproving,
+    /// J: Changeable, mending.
+    ChangeableMending,
+    /// K: Fairly fine, showers likely.
+    FairlyFineShowersLikely,
+    /// L: Rather unsettled, clearing later.
+    RatherUnsettledClearingLater,
+    /// M: Unsettled, probably improving.
+    UnsettledProbablyImproving,
+    /// N: Showery, bright intervals.
+    ShoweryBrightIntervals,
+    /// O: Showery, becoming less settled.
+    ShoweryBecomingLessSettled,
+    /// P: Changeable, some rain.
+    ChangeableSomeRain,
+    /// Q: Unsettled, short fine intervals.
+    UnsettledShortFineIntervals,
+    /// R: Unsettled, rain later.
+    UnsettledRainLater,
+    /// S: Unsettled, some rain.
+    UnsettledSomeRain,
+    /// T: Mostly very unsettled.
+    MostlyVeryUnsettled,
+    /// U: Occasional rain, worsening.
+    OccasionalRainWorsening,
+    /// V: Rain at times, very unsettled.
+    RainAtTimesVeryUnsettled,
+    /// W: Rain at frequent intervals.
+    RainAtFrequentIntervals,
+    /// X: Rain, very unsettled.
+    RainVeryUnsettled,
+    /// Y: Stormy, may improve.
+    StormyMayImprove,
+    /// Z: Stormy, much rain.
+    StormyMuchRain,
+}
+
+impl Forecast {
+    const ALL: [Forecast; 26] = [
+        Forecast::SettledFine,
+        Forecast::Fine,
+        Forecast::BecomingFine,
+        Forecast::FineBecomingLessSettled,
+        Forecast::FinePossibleShowers,
+        Forecast::FairlyFineImproving,
+        Forecast::FairlyFinePossibleShowersEarly,
+        Forecast::FairlyFineShoweryLater,
+        Forecast::ShoweryEarlyImproving,
+        Forecast::ChangeableMending,
+        Forecast::FairlyFineShowersLikely,
+        Forecast::RatherUnsettledClearingLater,
+        Forecast::UnsettledProbablyImproving,
+        Forecast::ShoweryBrightIntervals,
+        Forecast::ShoweryBecomingLessSettled,
+        Forecast::ChangeableSomeRain,
+        Forecast::UnsettledShortFineIntervals,
+        Forecast::UnsettledRainLater,
+        Forecast::UnsettledSomeRain,
+        Forecast::MostlyVeryUnsettled,
+        Forecast::OccasionalRainWorsening,
+        Forecast::RainAtTimesVeryUnsettled,
+        Forecast::RainAtFrequentIntervals,
+        Forecast::RainVeryUnsettled,
+        Forecast::StormyMayImprove,
+        Forecast::StormyMuchRain,
+    ];
+
+    const DESCRIPTIONS: [&'static str; 26] = [
+        "Settled fine",
+        "Fine weather",
+        "Becoming fine",
+        "Fine, becoming less settled",
+        "Fine, possible showers",
+        "Fairly fine, improving",
+        "Fairly fine, possible showers early",
+        "Fairly fine, showery later",
+        "Showery early, improving",
+        "Changeable, mending",
+        "Fairly fine, showers likely",
+        "Rather unsettled, clearing later",
+        "Unsettled, probably improving",
+        "Showery, bright intervals",
+        "Showery, becoming less settled",
+        "Changeable, some rain",
+        "Unsettled, short fine intervals",
+        "Unsettled, rain later",
+        "Unsettled, some rain",
+        "Mostly very unsettled",
+        "Occasional rain, worsening",
+        "Rain at times, very unsettled",
+        "Rain at frequent intervals",
+        "Rain, very unsettled",
+        "Stormy, may improve",
+        "Stormy, much rain",
+    ];
+
+    fn from_letter(letter: u8) -> Self {
+        Forecast::ALL[(letter - b'A') as usize]
+    }
+
+    /// Get the Zambretti letter of the forecast, from `'A'` to `'Z'`.
+    pub fn letter(self) -> char {
+        (b'A' + self as u8) as char
+    }
+}
+
+impl Display for Forecast {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(Forecast::DESCRIPTIONS[*self as usize])
+    }
+}
+
+/// Zambretti letters for falling, steady and rising pressure, from high to low pressure.
+const FALLING_FORECASTS: &[u8] = b"ABDHORUXZ";
+const STEADY_FORECASTS: &[u8] = b"ABEKNPSWXZ";
+const RISING_FORECASTS: &[u8] = b"ABCFGIJLMQTYY";
+
+/// Forecast the weather of the next 12 hours with the Zambretti algorithm, for the northern hemisphere
+/// and without wind direction or seasonal adjustments.
+///
+/// ### Arguments
+///
+/// * `sea_level_pressure` - Pressure reduced to sea level in pascals (Pa), see [`qff`].
+/// * `trend` - [`Trend`] of the pressure over the last 3 hours, see [`PressureHistory::tendency`].
+///
+/// ### Returns
+///
+/// The [`Forecast`]
+pub fn forecast(sea_level_pressure: i32, trend: Trend) -> Forecast {
+    let hpa = sea_level_pressure as f32 / 100.0;
+    let (z, first, letters) = match trend {
+        Trend::Falling => (127.0 - 0.12 * hpa, 1, FALLING_FORECASTS),
+        Trend::Steady => (144.0 - 0.13 * hpa, 10, STEADY_FORECASTS),
+        Trend::Rising => (185.0 - 0.16 * hpa, 20, RISING_FORECASTS),
+    };
+    let index = (libm::roundf(z) as i32 - first).clamp(0, letters.len() as i32 - 1);
+    Forecast::from_letter(letters[index as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fills a history with a reading every 10 minutes over 3 hours, changing by `first` Pa in
+    /// the first half and `second` Pa in the second half.
+    fn history(first: i32, second: i32) -> PressureHistory<32> {
+        let mut history = PressureHistory::new();
+        for step in 0..=18 {
+            let pressure = match step {
+                0..=9 => 101_000 + first * step / 9,
+                _ => 101_000 + first + second * (step - 9) / 9,
+            };
+            history.push(1000 + step as u32 * 600, pressure);
+        }
+        history
+    }
+
+    #[test]
+    fn tendency_needs_three_hours_of_readings() {
+        let mut history = PressureHistory::<32>::new();
+        assert_eq!(history.tendency(), None);
+        for step in 0..=12 {
+            history.push(step * 600, 101_000);
+        }
+        assert_eq!(history.tendency(), None);
+        for step in 13..=18 {
+            history.push(step * 600, 101_000);
+        }
+        assert!(history.tendency().is_some());
+        history.clear();
+        assert_eq!(history.tendency(), None);
+    }
+
+    #[test]
+    fn calculates_tendency() {
+        assert_eq!(
+            history(-150, -150).tendency(),
+            Some(Tendency {
+                change: -300,
+                characteristic: Characteristic::Decreasing,
+                trend: Trend::Falling,
+            })
+        );
+        assert_eq!(
+            history(50, 40).tendency(),
+            Some(Tendency {
+                change: 90,
+                characteristic: Characteristic::Increasing,
+                trend: Trend::Steady,
+            })
+        );
+    }
+
+    #[test]
+    fn classifies_wmo_characteristics() {
+        let code = |first, second| {
+            history(first, second)
+                .tendency()
+                .unwrap()
+                .characteristic
+                .code()
+        };
+
+        assert_eq!(code(100, -60), 0);
+        assert_eq!(code(100, 5), 1);
+        assert_eq!(code(100, 100), 2);
+        assert_eq!(code(0, 100), 3);
+        assert_eq!(code(20, 100), 3);
+        assert_eq!(code(5, -5), 4);
+        assert_eq!(code(-100, 60), 5);
+        assert_eq!(code(-100, 0), 6);
+        assert_eq!(code(-100, -100), 7);
+        assert_eq!(code(0, -100), 8);
+        assert_eq!(code(-20, -100), 8);
+    }
+
+    #[test]
+    fn tendency_survives_timestamp_wraparound() {
+        let mut history = PressureHistory::<32>::new();
+        for step in 0..=18u32 {
+            history.push(
+                (u32::MAX - 5000).wrapping_add(step * 600),
+                100_000 - step as i32 * 20,
+            );
+        }
+
+        assert_eq!(history.tendency().unwrap().trend, Trend::Falling);
+    }
+
+    #[test]
+    fn reduces_pressure_to_sea_level() {
+        // Standard atmosphere at 1000 m
+        assert!((qnh(89_875, 1000.0) - 101_325).abs() <= 5);
+        assert!((qff(89_875, 1000.0, 8.5) - 101_325).abs() <= 20);
+        // Colder air is denser, so the pressure increases faster towards sea level
+        assert!(qff(89_875, 1000.0, -10.0) > qff(89_875, 1000.0, 20.0));
+        assert_eq!(qnh(101_325, 0.0), 101_325);
+    }
+
+    #[test]
+    fn forecasts_with_zambretti() {
+        assert_eq!(forecast(103_500, Trend::Rising), Forecast::SettledFine);
+        assert_eq!(forecast(103_500, Trend::Steady), Forecast::SettledFine);
+        assert_eq!(
+            forecast(101_300, Trend::Steady),
+            Forecast::FinePossibleShowers
+        );
+        assert_eq!(
+            forecast(102_000, Trend::Falling),
+            Forecast::ShoweryBecomingLessSettled
+        );
+        assert_eq!(forecast(95_000, Trend::Falling), Forecast::StormyMuchRain);
+        assert_eq!(forecast(95_000, Trend::Falling).letter(), 'Z');
+    }
+
+    #[test]
+    fn forecasts_are_in_letter_order() {
+        for (letter, forecast) in (b'A'..=b'Z').zip(Forecast::ALL) {
+            assert_eq!(Forecast::from_letter(letter), forecast);
+            assert_eq!(forecast.letter(), letter as char);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Simulated device and helpers shared by the integration tests.
+
+// Every test crate only uses some of the helpers
+#![allow(dead_code)]
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use bmp085_180_rs::Measurement;
+use embedded_hal::i2c::{ErrorKind, ErrorType, NoAcknowledgeSource, Operation};
+
+/// Address of the simulated multiplexer.
+pub const MUX_ADDRESS: u8 = 0x70;
+
+/// Address of the simulated devices.
+pub const BMP_ADDRESS: u8 = 0x77;
+
+/// Calibration registers `0xAA` to `0xBF` of the datasheet's example.
+pub const CALIBRATION: [u8; 22] = [
+    0x01, 0x98, 0xFF, 0xB8, 0xC7, 0xD1, 0x7F, 0xE5, 0x7F, 0xF5, 0x5A, 0x71, 0x18, 0x2E, 0x00, 0x04,
+    0x80, 0x00, 0xDD, 0xF9, 0x0B, 0x34,
+];
+
+/// Uncompensated temperature of the datasheet's example.
+pub const UT: u16 = 27898;
+
+/// Uncompensated pressure of the datasheet's example, with the lowest oversampling setting.
+pub const UP: u32 = 23843;
+
+/// Measurement compensated from the datasheet's example readings.
+pub const MEASUREMENT: Measurement = Measurement {
+    temperature: 15.0,
+    pressure: 69964,
+};
+
+/// Simulated BMP180, answering conversions with fixed uncompensated readings, `up` being the
+/// uncompensated pressure with the lowest oversampling setting.
+pub struct SimDevice {
+    registers: [u8; 256],
+    pointer: u8,
+    ut: u16,
+    up: u32,
+    /// Control register value of every conversion started, shared to be checked once the device is moved.
+    pub conversions: Rc<Cell<Vec<u8>>>,
+}
+
+impl SimDevice {
+    pub fn new(ut: u16, up: u32) -> Self {
+        let mut registers = [0; 256];
+        registers[0xAA..=0xBF].copy_from_slice(&CALIBRATION);
+        registers[0xD0] = 0x55;
+        SimDevice {
+            registers,
+            pointer: 0,
+            ut,
+            up,
+            conversions: Rc::default(),
+        }
+    }
+
+    /// Device answering every conversion with the datasheet's example readings.
+    pub fn datasheet() -> Self {
+        SimDevice::new(UT, UP)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        let Some((&reg, values)) = bytes.split_first() else {
+            return;
+        };
+        self.pointer = reg;
+        for &value in values {
+            self.registers[self.pointer as usize] = value;
+            if self.pointer == 0xF4 {
+                self.convert(value);
+            }
+            self.pointer = self.pointer.wrapping_add(1);
+        }
+    }
+
+    pub fn read(&mut self, buffer: &mut [u8]) {
+        for byte in buffer {
+            *byte = self.registers[self.pointer as usize];
+            self.pointer = self.pointer.wrapping_add(1);
+        }
+    }
+
+    /// Runs the operations of an I2C transaction addressed to the device.
+    pub fn run(&mut self, operations: &mut [Operation<'_>]) {
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => self.write(bytes),
+                Operation::Read(buffer) => self.read(buffer),
+            }
+        }
+    }
+
+    fn convert(&mut self, ctrl_meas: u8) {
+        let mut conversions = self.conversions.take();
+        conversions.push(ctrl_meas);
+        self.conversions.set(conversions);
+
+        // The same pressure is measured with more resolution by higher oversampling settings
+        let out = match ctrl_meas {
+            0x2E => (self.ut as u32) << 8,
+            _ => self.up << 8,
+        };
+        self.registers[0xF6..=0xF8].copy_from_slice(&out.to_be_bytes()[1..]);
+    }
+}
+
+impl ErrorType for SimDevice {
+    type Error = ErrorKind;
+}
+
+impl embedded_hal::i2c::I2c for SimDevice {
+    fn transaction(
+        &mut self,
+        _address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.run(operations);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for SimDevice {
+    async fn transaction(
+        &mut self,
+        _address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.run(operations);
+        Ok(())
+    }
+}
+
+/// Simulated bus with a TCA9548A multiplexer, and devices on some of its channels.
+pub struct SimBus {
+    pub enabled: u8,
+    devices: [Option<SimDevice>; 8],
+    pub mux_writes: usize,
+    pub failing_mux_writes: usize,
+}
+
+impl SimBus {
+    pub fn new(devices: [Option<SimDevice>; 8]) -> Self {
+        SimBus {
+            enabled: 0,
+            devices,
+            mux_writes: 0,
+            failing_mux_writes: 0,
+        }
+    }
+
+    fn mux_transaction(&mut self, operations: &mut [Operation<'_>]) -> Result<(), ErrorKind> {
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    self.mux_writes += 1;
+                    if self.failing_mux_writes > 0 {
+                        self.failing_mux_writes -= 1;
+                        return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data));
+                    }
+                    if let Some(&channels) = bytes.last() {
+                        self.enabled = channels;
+                    }
+                }
+                Operation::Read(buffer) => buffer.fill(self.enabled),
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs an I2C transaction, reaching the multiplexer or the device on the enabled channel.
+    pub fn run(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), ErrorKind> {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        if address == MUX_ADDRESS {
+            return self.mux_transaction(operations);
+        }
+        if address != BMP_ADDRESS {
+            return Err(nack);
+        }
+
+        let enabled = self.enabled;
+        let mut devices = self
+            .devices
+            .iter_mut()
+            .enumerate()
+            .filter(|(channel, _)| enabled & (1 << channel) != 0)
+            .filter_map(|(_, device)| device.as_mut());
+        let device = match (devices.next(), devices.next()) {
+            (Some(device), None) => device,
+            (None, _) => return Err(nack),
+            // Devices sharing an address on several enabled channels collide
+            (Some(_), Some(_)) => return Err(ErrorKind::Bus),
+        };
+        device.run(operations);
+        Ok(())
+    }
+}
+
+impl ErrorType for SimBus {
+    type Error = ErrorKind;
+}
+
+impl embedded_hal::i2c::I2c for SimBus {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.run(address, operations)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for SimBus {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        // Let other futures run while the transaction is on the bus
+        embassy_futures::yield_now().await;
+        self.run(address, operations)
+    }
+}
+
+/// Delay which keeps track of the total time waited.
+#[derive(Clone, Default)]
+pub struct RecordingDelay(Rc<Cell<u64>>);
+
+impl RecordingDelay {
+    pub fn total_us(&self) -> u64 {
+        self.0.get() / 1000
+    }
+}
+
+impl embedded_hal::delay::DelayNs for RecordingDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.set(self.0.get() + ns as u64);
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for RecordingDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.0.set(self.0.get() + ns as u64);
+    }
+}
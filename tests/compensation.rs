@@ -0,0 +1,61 @@
+mod common;
+
+use bmp085_180_rs::compensation::{
+    calculate_altitude, calculate_pressure, calculate_temperature, compensate, parse_calibration,
+    CompensationError, Measurement, Oss, RawSample,
+};
+use common::CALIBRATION;
+
+#[test]
+fn parses_calibration_registers() {
+    let calib_data = parse_calibration(&CALIBRATION).unwrap();
+
+    assert_eq!(calib_data.ac1, 408);
+    assert_eq!(calib_data.ac4, 32741);
+    assert_eq!(calib_data.mb, -32768);
+    assert_eq!(calib_data.md, 2868);
+}
+
+#[test]
+fn rejects_unprogrammed_calibration_registers() {
+    let mut registers = CALIBRATION;
+    registers[20..].copy_from_slice(&[0xFF, 0xFF]);
+
+    assert_eq!(
+        parse_calibration(&registers),
+        Err(CompensationError::InvalidCalibration)
+    );
+    assert_eq!(
+        parse_calibration(&[0; 22]),
+        Err(CompensationError::InvalidCalibration)
+    );
+}
+
+#[test]
+fn compensates_recorded_readings() {
+    let calib_data = parse_calibration(&CALIBRATION).unwrap();
+    let sample = RawSample {
+        ut: 27898,
+        up: 23843,
+        oss: Oss::LowPower,
+    };
+
+    assert_eq!(
+        compensate(&calib_data, sample),
+        Ok(Measurement {
+            temperature: 15.0,
+            pressure: 69964
+        })
+    );
+}
+
+#[test]
+fn compensates_step_by_step() {
+    let calib_data = parse_calibration(&CALIBRATION).unwrap();
+    let (temperature, b5) = calculate_temperature(&calib_data, 27898).unwrap();
+    let pressure = calculate_pressure(&calib_data, Oss::LowPower, b5, 23843).unwrap();
+
+    assert_eq!(temperature, 15.0);
+    assert_eq!(pressure, 69964);
+    assert!((calculate_altitude(pressure, 101_325) - 3017.0).abs() < 1.0);
+}
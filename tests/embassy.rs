@@ -0,0 +1,236 @@
+mod common;
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use bmp085_180_rs::sampler::{Publisher, Sampler, SamplerConfig, SamplerControl};
+use bmp085_180_rs::{Health, Measurement, Oss, BMP};
+use common::{SimDevice, MEASUREMENT};
+use embassy_executor::{Executor, Spawner};
+use embassy_futures::select::select;
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::channel::Channel;
+use embassy_sync::pubsub::PubSubChannel;
+use embassy_sync::signal::Signal;
+use embassy_time::{with_timeout, Delay, Duration, Instant, Timer};
+
+type Measurements = Channel<NoopRawMutex, Measurement, 4>;
+type Latest = Signal<NoopRawMutex, Measurement>;
+type Broadcast = PubSubChannel<NoopRawMutex, Measurement, 2, 1, 0>;
+
+/// State shared by the tasks of a test, leaked to live as long as the executor.
+struct Shared<P: 'static> {
+    control: SamplerControl<NoopRawMutex>,
+    publisher: P,
+    stop: Signal<NoopRawMutex, ()>,
+    health: Signal<NoopRawMutex, Health>,
+}
+
+impl<P> Shared<P> {
+    fn new(config: SamplerConfig, publisher: P) -> &'static Self {
+        Box::leak(Box::new(Shared {
+            control: SamplerControl::new(config),
+            publisher,
+            stop: Signal::new(),
+            health: Signal::new(),
+        }))
+    }
+}
+
+/// Samples with a driver on `device` until stopped, then signals the driver's health.
+async fn sample<P: Publisher>(device: SimDevice, shared: &Shared<P>) {
+    let bmp = BMP::new(device, Delay, Default::default()).init().await;
+    let mut sampler = Sampler::new(bmp.unwrap());
+    select(
+        sampler.run(&shared.control, &shared.publisher),
+        shared.stop.wait(),
+    )
+    .await;
+    shared.health.signal(sampler.release().health());
+}
+
+#[embassy_executor::task(pool_size = 3)]
+async fn sample_to_channel(device: SimDevice, shared: &'static Shared<Measurements>) {
+    sample(device, shared).await
+}
+
+#[embassy_executor::task]
+async fn sample_to_signal(device: SimDevice, shared: &'static Shared<Latest>) {
+    sample(device, shared).await
+}
+
+#[embassy_executor::task]
+async fn sample_to_pubsub(device: SimDevice, shared: &'static Shared<Broadcast>) {
+    sample(device, shared).await
+}
+
+/// Runs the tasks spawned by `spawn` on an `embassy-executor` std executor in its own thread, until the
+/// test's task reports it is done.
+fn run_test(spawn: impl FnOnce(Spawner, Sender<()>) + Send + 'static) {
+    let (done, finished) = mpsc::channel();
+    thread::spawn(move || {
+        let executor = Box::leak(Box::new(Executor::new()));
+        executor.run(|spawner| spawn(spawner, done))
+    });
+
+    // A failed assertion takes the executor thread down before the test's task is done
+    finished
+        .recv_timeout(std::time::Duration::from_secs(5))
+        .expect("test task didn't complete");
+}
+
+#[test]
+fn sampler_publishes_to_channel_at_configured_rate() {
+    #[embassy_executor::task]
+    async fn check(shared: &'static Shared<Measurements>, done: Sender<()>) {
+        let started = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(shared.publisher.receive().await, MEASUREMENT);
+        }
+        assert!(started.elapsed() >= Duration::from_millis(40));
+
+        shared.stop.signal(());
+        assert_eq!(shared.health.wait().await.consecutive_failures, 0);
+        done.send(()).unwrap();
+    }
+
+    run_test(|spawner, done| {
+        let config = SamplerConfig {
+            period: Duration::from_millis(20),
+            oss: Oss::LowPower,
+        };
+        let shared = Shared::new(config, Channel::new());
+        spawner.spawn(check(shared, done)).unwrap();
+        spawner
+            .spawn(sample_to_channel(SimDevice::datasheet(), shared))
+            .unwrap();
+    });
+}
+
+#[test]
+fn sampler_publishes_to_signal() {
+    #[embassy_executor::task]
+    async fn check(shared: &'static Shared<Latest>, done: Sender<()>) {
+        assert_eq!(shared.publisher.wait().await, MEASUREMENT);
+
+        shared.stop.signal(());
+        done.send(()).unwrap();
+    }
+
+    run_test(|spawner, done| {
+        let config = SamplerConfig {
+            period: Duration::from_millis(5),
+            oss: Oss::LowPower,
+        };
+        let shared = Shared::new(config, Signal::new());
+        spawner.spawn(check(shared, done)).unwrap();
+        spawner
+            .spawn(sample_to_signal(SimDevice::datasheet(), shared))
+            .unwrap();
+    });
+}
+
+#[test]
+fn sampler_publishes_to_pubsub() {
+    #[embassy_executor::task]
+    async fn check(shared: &'static Shared<Broadcast>, done: Sender<()>) {
+        let mut subscriber = shared.publisher.subscriber().unwrap();
+        assert_eq!(subscriber.next_message_pure().await, MEASUREMENT);
+
+        shared.stop.signal(());
+        done.send(()).unwrap();
+    }
+
+    run_test(|spawner, done| {
+        let config = SamplerConfig {
+            period: Duration::from_millis(5),
+            oss: Oss::LowPower,
+        };
+        let shared = Shared::new(config, PubSubChannel::new());
+        // Spawned first, so that it subscribes before the first measurement is published
+        spawner.spawn(check(shared, done)).unwrap();
+        spawner
+            .spawn(sample_to_pubsub(SimDevice::datasheet(), shared))
+            .unwrap();
+    });
+}
+
+#[test]
+fn sampler_stops_and_starts() {
+    #[embassy_executor::task]
+    async fn check(shared: &'static Shared<Measurements>, done: Sender<()>) {
+        let (control, channel) = (&shared.control, &shared.publisher);
+        channel.receive().await;
+        control.stop();
+        Timer::after_millis(1).await;
+        channel.clear();
+        assert!(!control.is_running());
+        assert!(with_timeout(Duration::from_millis(100), channel.receive())
+            .await
+            .is_err());
+
+        control.start();
+        assert_eq!(
+            with_timeout(Duration::from_millis(100), channel.receive()).await,
+            Ok(MEASUREMENT)
+        );
+
+        shared.stop.signal(());
+        done.send(()).unwrap();
+    }
+
+    run_test(|spawner, done| {
+        let config = SamplerConfig {
+            period: Duration::from_millis(5),
+            oss: Oss::LowPower,
+        };
+        let shared = Shared::new(config, Channel::new());
+        spawner.spawn(check(shared, done)).unwrap();
+        spawner
+            .spawn(sample_to_channel(SimDevice::datasheet(), shared))
+            .unwrap();
+    });
+}
+
+#[test]
+fn sampler_is_reconfigured() {
+    #[embassy_executor::task]
+    async fn check(
+        shared: &'static Shared<Measurements>,
+        conversions: Rc<Cell<Vec<u8>>>,
+        done: Sender<()>,
+    ) {
+        let (control, channel) = (&shared.control, &shared.publisher);
+        channel.receive().await;
+        let config = SamplerConfig {
+            period: Duration::from_secs(60),
+            oss: Oss::UltraHighRes,
+        };
+        control.reconfigure(config);
+        assert_eq!(control.config(), config);
+        // Takes a measurement right away, rather than at the end of the long period
+        assert!(with_timeout(Duration::from_millis(100), channel.receive())
+            .await
+            .is_ok());
+
+        shared.stop.signal(());
+        shared.health.wait().await;
+        assert_eq!(conversions.take(), vec![0x2E, 0x34, 0x2E, 0xF4]);
+        done.send(()).unwrap();
+    }
+
+    run_test(|spawner, done| {
+        let config = SamplerConfig {
+            period: Duration::from_secs(60),
+            oss: Oss::LowPower,
+        };
+        let shared = Shared::new(config, Channel::new());
+        let device = SimDevice::datasheet();
+        spawner
+            .spawn(check(shared, device.conversions.clone(), done))
+            .unwrap();
+        spawner.spawn(sample_to_channel(device, shared)).unwrap();
+    });
+}
@@ -0,0 +1,105 @@
+use bmp085_180_rs::filter::{AltitudeKalman, Ema, KalmanConfig};
+use bmp085_180_rs::logic::calculate_altitude;
+
+/// Pressure trace at rest, climbing, then at rest again, see the header of the file.
+const TRACE: &str = include_str!("data/pressure_trace.csv");
+
+/// Time between the readings of the trace, in s.
+const DT: f32 = 0.1;
+
+/// Readings of the trace taken at rest before the climb.
+const BEFORE_CLIMB: std::ops::Range<usize> = 0..300;
+
+/// Readings of the trace taken at rest after the climb.
+const AFTER_CLIMB: std::ops::Range<usize> = 400..600;
+
+/// Readings of the trace, once a filter has settled at rest.
+const SETTLED: std::ops::Range<usize> = 100..300;
+
+/// Altitudes of the trace's readings, as the driver calculates them.
+fn trace_altitudes() -> Vec<f32> {
+    TRACE
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .skip(1)
+        .map(|line| {
+            let (_, pressure) = line.split_once(',').unwrap();
+            calculate_altitude(pressure.parse().unwrap(), 101_325)
+        })
+        .collect()
+}
+
+fn mean(values: &[f32]) -> f32 {
+    values.iter().sum::<f32>() / values.len() as f32
+}
+
+fn variance(values: &[f32]) -> f32 {
+    let mean = mean(values);
+    values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f32>()
+        / values.len() as f32
+}
+
+/// Time in s at which `altitudes` first get halfway from the altitude before the climb to the one after.
+fn halfway_time(altitudes: &[f32], raw: &[f32]) -> f32 {
+    let halfway = (mean(&raw[BEFORE_CLIMB]) + mean(&raw[AFTER_CLIMB])) / 2.0;
+    let index = altitudes.iter().position(|&altitude| altitude >= halfway);
+    index.unwrap() as f32 * DT
+}
+
+/// Time in s by which `filtered` altitudes lag behind the raw ones through the climb, measured halfway,
+/// with the raw altitudes averaged over 1 s around each reading.
+fn climb_lag(filtered: &[f32], raw: &[f32]) -> f32 {
+    let averaged: Vec<f32> = (0..raw.len())
+        .map(|i| mean(&raw[i.saturating_sub(5)..(i + 5).min(raw.len())]))
+        .collect();
+    halfway_time(filtered, raw) - halfway_time(&averaged, raw)
+}
+
+#[test]
+fn ema_reduces_noise_of_trace() {
+    let raw = trace_altitudes();
+    let mut ema = Ema::new(0.1);
+    let filtered: Vec<f32> = raw.iter().map(|&altitude| ema.update(altitude)).collect();
+
+    assert!(variance(&filtered[SETTLED]) < variance(&raw[SETTLED]) / 10.0);
+    assert!(climb_lag(&filtered, &raw) < 1.5);
+}
+
+#[test]
+fn kalman_reduces_noise_of_trace() {
+    let raw = trace_altitudes();
+    let mut kalman = AltitudeKalman::new(KalmanConfig::DEFAULT);
+    let estimates: Vec<_> = raw
+        .iter()
+        .map(|&altitude| kalman.update(altitude, DT))
+        .collect();
+    let filtered: Vec<f32> = estimates.iter().map(|estimate| estimate.altitude).collect();
+
+    assert!(variance(&filtered[SETTLED]) < variance(&raw[SETTLED]) / 5.0);
+    assert!(estimates[SETTLED]
+        .iter()
+        .all(|estimate| estimate.vertical_speed.abs() < 0.5));
+    // Tracking vertical speed, it keeps up with the climb
+    assert!(climb_lag(&filtered, &raw) < 0.5);
+}
+
+#[test]
+fn kalman_tracks_climb_of_trace() {
+    let raw = trace_altitudes();
+    let mut kalman = AltitudeKalman::new(KalmanConfig::DEFAULT);
+    let estimates: Vec<_> = raw
+        .iter()
+        .map(|&altitude| kalman.update(altitude, DT))
+        .collect();
+
+    // 2 m/s over the second half of the climb
+    assert!(estimates[350..400]
+        .iter()
+        .all(|estimate| (estimate.vertical_speed - 2.0).abs() < 0.6));
+    let settled = estimates.last().unwrap();
+    assert!((settled.altitude - mean(&raw[AFTER_CLIMB])).abs() < 0.5);
+    assert!(settled.vertical_speed.abs() < 0.5);
+}
@@ -1,7 +1,19 @@
-use bmp085_180_rs::{BMPError, Config, Oss, BMP};
-use embedded_hal::i2c::ErrorKind;
+#![cfg(not(feature = "async"))]
+
+mod common;
+
+use bmp085_180_rs::logger::MeasurementLog;
+use bmp085_180_rs::scan::{probe, scan_mux};
+use bmp085_180_rs::{
+    compensate, BMPError, BmpArray, CompensationAlgorithm, Config, ConversionTiming, Health,
+    Measurement, Operation, Oss, PlausibilityLimits, RawSample, RetryPolicy, TransactionStats,
+    Uninitialized, BMP,
+};
+use common::{RecordingDelay, CALIBRATION};
+use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
 use embedded_hal_mock::eh1::delay::NoopDelay;
 use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+use std::cell::Cell;
 
 fn get_init_coeficient_expectations() -> Vec<I2cTransaction> {
     (0xAA..=0xBF)
@@ -25,7 +37,7 @@ fn test_connection_fails_with_invalid_id() {
     let mut i2c = I2cMock::new(&expectations);
     let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
 
-    assert_eq!(bmp.test_connection(), Err(BMPError::InvalidDeviceId));
+    assert_eq!(bmp.test_connection(), Err(BMPError::InvalidDeviceId(0xFF)));
     i2c.done();
 }
 
@@ -36,7 +48,26 @@ fn test_connection_fails_if_i2c_error() {
     let mut i2c = I2cMock::new(&expectations);
     let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
 
-    assert_eq!(bmp.test_connection(), Err(BMPError::I2C(ErrorKind::Other)));
+    assert_eq!(
+        bmp.test_connection(),
+        Err(BMPError::I2C {
+            operation: Operation::Connect,
+            register: 0xD0,
+            source: ErrorKind::Other
+        })
+    );
+    i2c.done();
+}
+
+#[test]
+fn i2c_error_kind_is_passed_through() {
+    let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+    let expectations = [I2cTransaction::write_read(0x77, vec![0xD0], vec![0x55]).with_error(nack)];
+    let mut i2c = I2cMock::new(&expectations);
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
+
+    assert_eq!(bmp.test_connection().unwrap_err().kind(), Some(nack));
+    assert_eq!(BMPError::<ErrorKind>::InvalidDeviceId(0xFF).kind(), None);
     i2c.done();
 }
 
@@ -46,9 +77,9 @@ fn init_ok_given_calibration_values() {
         .map(|value| I2cTransaction::write_read(0x77, vec![value], vec![0xFF]))
         .collect::<Vec<_>>();
     let mut i2c = I2cMock::new(&expectations);
-    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
+    let bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
 
-    assert_eq!(bmp.init(), Ok(()));
+    assert!(bmp.init().is_ok());
     i2c.done();
 }
 
@@ -57,9 +88,16 @@ fn init_fails_if_i2c_error() {
     let expectations =
         [I2cTransaction::write_read(0x77, vec![0xAA], vec![0xFF]).with_error(ErrorKind::Bus)];
     let mut i2c = I2cMock::new(&expectations);
-    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
+    let bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
 
-    assert_eq!(bmp.init(), Err(BMPError::I2C(ErrorKind::Bus)));
+    assert_eq!(
+        bmp.init().err(),
+        Some(BMPError::I2C {
+            operation: Operation::Init,
+            register: 0xAA,
+            source: ErrorKind::Bus
+        })
+    );
     i2c.done();
 }
 
@@ -75,9 +113,9 @@ fn read_temperature_ok_given_readings() {
     ]
     .concat();
     let mut i2c = I2cMock::new(&expectations);
-    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
-
-    bmp.init().unwrap();
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
     let temperature = bmp.read_temperature();
 
     assert!(temperature.is_ok());
@@ -93,13 +131,17 @@ fn read_temperature_fails_if_i2c_error() {
     ]
     .concat();
     let mut i2c = I2cMock::new(&expectations);
-    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
-
-    bmp.init().unwrap();
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
 
     assert_eq!(
         bmp.read_temperature(),
-        Err(BMPError::I2C(ErrorKind::Overrun))
+        Err(BMPError::I2C {
+            operation: Operation::Temperature,
+            register: 0xF4,
+            source: ErrorKind::Overrun
+        })
     );
     i2c.done();
 }
@@ -120,9 +162,9 @@ fn read_pressure_ok_given_readings() {
     ]
     .concat();
     let mut i2c = I2cMock::new(&expectations);
-    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
-
-    bmp.init().unwrap();
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
     let pressure = bmp.read_pressure();
 
     assert!(pressure.is_ok());
@@ -150,9 +192,7 @@ fn read_pressure_highest_resolution_ok_given_readings() {
         oss: Oss::UltraHighRes,
         ..Config::default()
     };
-    let mut bmp = BMP::new(i2c.clone(), NoopDelay, bmp_config);
-
-    bmp.init().unwrap();
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, bmp_config).init().unwrap();
     let pressure = bmp.read_pressure();
 
     assert!(pressure.is_ok());
@@ -175,36 +215,646 @@ fn read_pressure_fails_if_i2c_error() {
     ]
     .concat();
     let mut i2c = I2cMock::new(&expectations);
-    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+
+    assert_eq!(
+        bmp.read_pressure(),
+        Err(BMPError::I2C {
+            operation: Operation::Pressure,
+            register: 0xF6,
+            source: ErrorKind::ArbitrationLoss
+        })
+    );
+    i2c.done();
+}
+
+#[test]
+fn read_raw_temperature_ok_given_readings() {
+    let expectations = [
+        get_init_coeficient_expectations(),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0x6C]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFA]),
+        ],
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+
+    assert_eq!(bmp.read_raw_temperature(), Ok(0x6CFA));
+    i2c.done();
+}
 
-    bmp.init().unwrap();
+#[test]
+fn read_raw_pressure_ok_given_readings() {
+    let expectations = [
+        get_init_coeficient_expectations(),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0xFF]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFF]),
+            I2cTransaction::write(0x77, vec![0xF4, 0xB4]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0x33]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0x38]),
+            I2cTransaction::write_read(0x77, vec![0xF8], vec![0xC0]),
+        ],
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let bmp_config = Config {
+        oss: Oss::HighRes,
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, bmp_config).init().unwrap();
 
+    assert_eq!(
+        bmp.read_raw_pressure(),
+        Ok(RawSample {
+            ut: 0xFFFF,
+            up: 0x3338C0 >> 6,
+            oss: Oss::HighRes,
+        })
+    );
+    i2c.done();
+}
+
+#[test]
+fn raw_pressure_compensates_to_read_pressure() {
+    let readings = vec![
+        I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+        I2cTransaction::write_read(0x77, vec![0xF6], vec![0xFF]),
+        I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFF]),
+        I2cTransaction::write(0x77, vec![0xF4, 0x34]),
+        I2cTransaction::write_read(0x77, vec![0xF6], vec![0x33]),
+        I2cTransaction::write_read(0x77, vec![0xF7], vec![0x38]),
+        I2cTransaction::write_read(0x77, vec![0xF8], vec![0x00]),
+    ];
+    let expectations = [
+        get_init_coeficient_expectations(),
+        readings.clone(),
+        readings,
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+    let sample = bmp.read_raw_pressure().unwrap();
+    let measurement = compensate(&bmp.calibration_data(), sample).unwrap();
+
+    assert_eq!(bmp.read_pressure(), Ok(measurement.pressure));
+    i2c.done();
+}
+
+#[test]
+fn read_pressure_fails_if_implausible() {
+    let readings = vec![
+        I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+        I2cTransaction::write_read(0x77, vec![0xF6], vec![0xFF]),
+        I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFF]),
+        I2cTransaction::write(0x77, vec![0xF4, 0x34]),
+        I2cTransaction::write_read(0x77, vec![0xF6], vec![0x33]),
+        I2cTransaction::write_read(0x77, vec![0xF7], vec![0x38]),
+        I2cTransaction::write_read(0x77, vec![0xF8], vec![0x00]),
+    ];
+    let expectations = [
+        get_init_coeficient_expectations(),
+        readings.clone(),
+        readings.clone(),
+        readings,
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+    let sample = bmp.read_raw_pressure().unwrap();
+    let measurement = compensate(&bmp.calibration_data(), sample).unwrap();
+
+    bmp.set_plausibility_limits(PlausibilityLimits {
+        max_pressure: measurement.pressure - 1,
+        ..PlausibilityLimits::NONE
+    });
     assert_eq!(
         bmp.read_pressure(),
-        Err(BMPError::I2C(ErrorKind::ArbitrationLoss))
+        Err(BMPError::OutOfRange(Operation::Pressure))
     );
+
+    bmp.set_plausibility_limits(PlausibilityLimits {
+        min_pressure: measurement.pressure,
+        max_pressure: measurement.pressure,
+        ..PlausibilityLimits::NONE
+    });
+    assert_eq!(bmp.read_pressure(), Ok(measurement.pressure));
     i2c.done();
 }
 
 #[test]
-fn read_altitude_ok_given_readings() {
+fn read_temperature_fails_if_implausible() {
+    let expectations = [
+        get_init_coeficient_expectations(),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0xFF]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFF]),
+        ],
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let bmp_config = Config {
+        limits: PlausibilityLimits {
+            max_temperature: -1000,
+            ..PlausibilityLimits::DATASHEET
+        },
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, bmp_config).init().unwrap();
+
+    assert_eq!(
+        bmp.read_temperature(),
+        Err(BMPError::OutOfRange(Operation::Temperature))
+    );
+    i2c.done();
+}
+
+thread_local! {
+    static NOW_MS: Cell<u32> = const { Cell::new(0) };
+}
+
+fn clock() -> u32 {
+    NOW_MS.get()
+}
+
+#[test]
+fn read_pressure_fails_if_sensor_is_stuck() {
+    let readings = vec![
+        I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+        I2cTransaction::write_read(0x77, vec![0xF6], vec![0xFF]),
+        I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFF]),
+        I2cTransaction::write(0x77, vec![0xF4, 0x34]),
+        I2cTransaction::write_read(0x77, vec![0xF6], vec![0x33]),
+        I2cTransaction::write_read(0x77, vec![0xF7], vec![0x38]),
+        I2cTransaction::write_read(0x77, vec![0xF8], vec![0x00]),
+    ];
+    let expectations = [
+        get_init_coeficient_expectations(),
+        readings.clone(),
+        readings.clone(),
+        readings[..3].to_vec(),
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let bmp_config = Config {
+        stuck_threshold: Some(3),
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, bmp_config).init().unwrap();
+    bmp.set_clock(clock);
+
+    NOW_MS.set(1000);
+    assert!(bmp.read_pressure().is_ok());
+    NOW_MS.set(2000);
+    assert!(bmp.read_pressure().is_ok());
+    NOW_MS.set(3000);
+    assert_eq!(
+        bmp.read_pressure(),
+        Err(BMPError::StuckSensor(Operation::Temperature))
+    );
+    assert_eq!(
+        bmp.health(),
+        Health {
+            measurements: 3,
+            last_success_ms: Some(2000),
+            consecutive_failures: 1,
+            stuck: true,
+        }
+    );
+    assert_eq!(bmp.health().since_last_success(3500), Some(1500));
+    i2c.done();
+}
+
+#[test]
+fn health_recovers_once_readings_change() {
+    let reading = |ut: u8| {
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0x6C]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![ut]),
+        ]
+    };
+    let expectations = [
+        get_init_coeficient_expectations(),
+        reading(0xFA),
+        reading(0xFA),
+        reading(0xFB),
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let bmp_config = Config {
+        stuck_threshold: Some(2),
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, bmp_config).init().unwrap();
+
+    assert_eq!(bmp.read_raw_temperature(), Ok(0x6CFA));
+    assert_eq!(
+        bmp.read_raw_temperature(),
+        Err(BMPError::StuckSensor(Operation::Temperature))
+    );
+    assert!(bmp.health().stuck);
+    assert_eq!(bmp.read_raw_temperature(), Ok(0x6CFB));
+    assert_eq!(
+        bmp.health(),
+        Health {
+            measurements: 3,
+            last_success_ms: None,
+            consecutive_failures: 0,
+            stuck: false,
+        }
+    );
+    i2c.done();
+}
+
+#[test]
+fn init_fails_given_stuck_threshold_below_2() {
+    for threshold in [0, 1] {
+        let bmp_config = Config {
+            stuck_threshold: Some(threshold),
+            ..Config::default()
+        };
+        let mut i2c = I2cMock::new(&[]);
+        let result = BMP::new(i2c.clone(), NoopDelay, bmp_config).init();
+
+        assert_eq!(result.err(), Some(BMPError::InvalidConfig));
+        i2c.done();
+    }
+}
+
+/// Expectations for a self-test, measuring the same pressure with every oversampling setting.
+fn get_self_test_expectations(id: u8, reset_error: Option<ErrorKind>) -> Vec<I2cTransaction> {
+    let mut reset = I2cTransaction::write(0x77, vec![0xE0, 0xB6]);
+    if let Some(err) = reset_error {
+        reset = reset.with_error(err);
+    }
+    let mut expectations = vec![I2cTransaction::write_read(0x77, vec![0xD0], vec![id])];
+    for ctrl_meas in [0x34, 0x74, 0xB4, 0xF4] {
+        expectations.extend([
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0xFF]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFF]),
+            I2cTransaction::write(0x77, vec![0xF4, ctrl_meas]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0x33]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0x38]),
+            I2cTransaction::write_read(0x77, vec![0xF8], vec![0x00]),
+        ]);
+    }
+    expectations.push(reset);
+    if reset_error.is_none() {
+        expectations.push(I2cTransaction::write_read(0x77, vec![0xD0], vec![id]));
+    }
+    expectations
+}
+
+#[test]
+fn self_test_passes_given_healthy_device() {
+    let expectations = [
+        get_init_coeficient_expectations(),
+        get_self_test_expectations(0x55, None),
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+    let report = bmp.self_test();
+
+    assert!(report.passed(), "{report:?}");
+    assert!(report.measurements.iter().all(Result::is_ok));
+    i2c.done();
+}
+
+#[test]
+fn self_test_ignores_repeated_readings_for_stuck_sensor_detection() {
+    let expectations = [
+        get_init_coeficient_expectations(),
+        get_self_test_expectations(0x55, None),
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let bmp_config = Config {
+        stuck_threshold: Some(2),
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, bmp_config).init().unwrap();
+    let report = bmp.self_test();
+
+    assert!(report.passed(), "{report:?}");
+    assert!(!bmp.health().stuck);
+    i2c.done();
+}
+
+#[test]
+fn self_test_reports_each_failed_check() {
+    let expectations = [
+        get_init_coeficient_expectations(),
+        get_self_test_expectations(0x00, Some(ErrorKind::Other)),
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+    let report = bmp.self_test();
+
+    assert!(!report.passed());
+    assert_eq!(report.connection, Err(BMPError::InvalidDeviceId(0x00)));
+    assert!(report.calibration);
+    assert!(report.measurements.iter().all(Result::is_ok));
+    assert!(report.consistent);
+    assert_eq!(
+        report.soft_reset,
+        Err(BMPError::I2C {
+            operation: Operation::Reset,
+            register: 0xE0,
+            source: ErrorKind::Other
+        })
+    );
+    i2c.done();
+}
+
+#[test]
+fn read_pressure_waits_datasheet_max_by_default() {
+    let expectations = [
+        get_init_coeficient_expectations(),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0xFF]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFF]),
+            I2cTransaction::write(0x77, vec![0xF4, 0xF4]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0x33]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0x38]),
+            I2cTransaction::write_read(0x77, vec![0xF8], vec![0x80]),
+        ],
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let delay = RecordingDelay::default();
+    let bmp_config = Config {
+        oss: Oss::UltraHighRes,
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), delay.clone(), bmp_config)
+        .init()
+        .unwrap();
+    bmp.read_pressure().unwrap();
+
+    assert_eq!(delay.total_us(), 4500 + 25_500);
+    i2c.done();
+}
+
+#[test]
+fn read_pressure_polls_sco_with_fast_timing() {
+    let expectations = [
+        get_init_coeficient_expectations(),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF4], vec![0x0E]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0xFF]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFF]),
+            I2cTransaction::write(0x77, vec![0xF4, 0x74]),
+            I2cTransaction::write_read(0x77, vec![0xF4], vec![0x74]),
+            I2cTransaction::write_read(0x77, vec![0xF4], vec![0x74]),
+            I2cTransaction::write_read(0x77, vec![0xF4], vec![0x54]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0x33]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0x38]),
+            I2cTransaction::write_read(0x77, vec![0xF8], vec![0x00]),
+        ],
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let delay = RecordingDelay::default();
+    let bmp_config = Config {
+        oss: Oss::Standard,
+        timing: ConversionTiming::FAST,
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), delay.clone(), bmp_config)
+        .init()
+        .unwrap();
+
+    assert!(bmp.read_pressure().is_ok());
+    assert_eq!(delay.total_us(), 3000 + 5000 + 2 * 500);
+    i2c.done();
+}
+
+#[test]
+fn read_temperature_times_out_polling_sco_after_datasheet_max() {
+    let expectations = [
+        get_init_coeficient_expectations(),
+        vec![I2cTransaction::write(0x77, vec![0xF4, 0x2E])],
+        vec![I2cTransaction::write_read(0x77, vec![0xF4], vec![0x2E]); 4],
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let delay = RecordingDelay::default();
+    let bmp_config = Config {
+        timing: ConversionTiming::FAST,
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), delay.clone(), bmp_config)
+        .init()
+        .unwrap();
+
+    assert_eq!(
+        bmp.read_temperature(),
+        Err(BMPError::ConversionTimeout(Operation::Temperature))
+    );
+    assert_eq!(delay.total_us(), 4500);
+    i2c.done();
+}
+
+#[test]
+fn read_temperature_polls_sco_at_configured_interval() {
     let expectations = [
         get_init_coeficient_expectations(),
         vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF4], vec![0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF4], vec![0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF4], vec![0x0E]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0xFF]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFF]),
+        ],
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let delay = RecordingDelay::default();
+    let bmp_config = Config {
+        timing: ConversionTiming::polling(250),
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), delay.clone(), bmp_config)
+        .init()
+        .unwrap();
+
+    assert!(bmp.read_temperature().is_ok());
+    assert_eq!(delay.total_us(), 2 * 250);
+    i2c.done();
+}
+
+#[test]
+fn sco_polling_is_bounded_by_configured_wait_if_longer() {
+    let timing = ConversionTiming {
+        temperature_us: 6000,
+        ..ConversionTiming::FAST
+    };
+    let expectations = [
+        get_init_coeficient_expectations(),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF4], vec![0x2E]),
+        ],
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let delay = RecordingDelay::default();
+    let bmp_config = Config {
+        timing,
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), delay.clone(), bmp_config)
+        .init()
+        .unwrap();
+
+    assert_eq!(
+        bmp.read_temperature(),
+        Err(BMPError::ConversionTimeout(Operation::Temperature))
+    );
+    assert_eq!(delay.total_us(), 6000);
+    i2c.done();
+}
+
+#[test]
+fn register_access_succeeds_after_retry() {
+    let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+    let expectations = [
+        I2cTransaction::write_read(0x77, vec![0xD0], vec![0x55]).with_error(nack),
+        I2cTransaction::write_read(0x77, vec![0xD0], vec![0x55]),
+    ];
+    let mut i2c = I2cMock::new(&expectations);
+    let delay = RecordingDelay::default();
+    let bmp_config = Config {
+        retry: RetryPolicy {
+            max_attempts: 3,
+            backoff_us: 100,
+        },
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), delay.clone(), bmp_config);
+
+    assert_eq!(bmp.test_connection(), Ok(()));
+    assert_eq!(delay.total_us(), 100);
+    assert_eq!(
+        bmp.transaction_stats(),
+        TransactionStats {
+            retried: 1,
+            failed: 0
+        }
+    );
+    i2c.done();
+}
+
+#[test]
+fn register_access_fails_after_max_attempts() {
+    let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data);
+    let expectations = vec![I2cTransaction::write(0x77, vec![0xE0, 0xB6]).with_error(nack); 3];
+    let mut i2c = I2cMock::new(&expectations);
+    let delay = RecordingDelay::default();
+    let bmp_config = Config {
+        retry: RetryPolicy {
+            max_attempts: 3,
+            backoff_us: 100,
+        },
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), delay.clone(), bmp_config);
+
+    assert_eq!(
+        bmp.soft_reset(),
+        Err(BMPError::I2C {
+            operation: Operation::Reset,
+            register: 0xE0,
+            source: nack
+        })
+    );
+    assert_eq!(delay.total_us(), 100 + 200);
+    assert_eq!(
+        bmp.transaction_stats(),
+        TransactionStats {
+            retried: 0,
+            failed: 1
+        }
+    );
+    i2c.done();
+}
+
+#[test]
+fn read_pressure_recovers_from_bus_glitches() {
+    let expectations = [
+        get_init_coeficient_expectations(),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]).with_error(ErrorKind::Bus),
             I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
             I2cTransaction::write_read(0x77, vec![0xF6], vec![0xFF]),
             I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFF]),
             I2cTransaction::write(0x77, vec![0xF4, 0x34]),
             I2cTransaction::write_read(0x77, vec![0xF6], vec![0x33]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0x38])
+                .with_error(ErrorKind::ArbitrationLoss),
             I2cTransaction::write_read(0x77, vec![0xF7], vec![0x38]),
             I2cTransaction::write_read(0x77, vec![0xF8], vec![0x00]),
         ],
     ]
     .concat();
     let mut i2c = I2cMock::new(&expectations);
-    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
+    let bmp_config = Config {
+        retry: RetryPolicy {
+            max_attempts: 2,
+            backoff_us: 0,
+        },
+        ..Config::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, bmp_config).init().unwrap();
+
+    assert!(bmp.read_pressure().is_ok());
+    assert_eq!(bmp.transaction_stats().retried, 2);
+    i2c.done();
+}
 
-    bmp.init().unwrap();
+#[test]
+fn read_altitude_ok_given_readings() {
+    let expectations = [
+        get_init_coeficient_expectations(),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0xFF]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFF]),
+            I2cTransaction::write(0x77, vec![0xF4, 0x34]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0x33]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0x38]),
+            I2cTransaction::write_read(0x77, vec![0xF8], vec![0x00]),
+        ],
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
     let altitude = bmp.read_altitude();
 
     assert!(altitude.is_ok());
@@ -212,6 +862,109 @@ fn read_altitude_ok_given_readings() {
     i2c.done();
 }
 
+#[test]
+fn log_measurement_only_measures_when_due() {
+    let expectations = [
+        get_init_coeficient_expectations(),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0xFF]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFF]),
+            I2cTransaction::write(0x77, vec![0xF4, 0x34]),
+            I2cTransaction::write_read(0x77, vec![0xF6], vec![0x33]),
+            I2cTransaction::write_read(0x77, vec![0xF7], vec![0x38]),
+            I2cTransaction::write_read(0x77, vec![0xF8], vec![0x00]),
+        ],
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+    let mut log = MeasurementLog::<4>::new(60_000);
+
+    assert_eq!(bmp.log_measurement(&mut log, 0), Ok(true));
+    assert_eq!(bmp.log_measurement(&mut log, 30_000), Ok(false));
+    assert_eq!(log.len(), 1);
+    i2c.done();
+}
+
+/// Measurement of 15.0 ºC and 69964 Pa, with the datasheet's calibration data.
+fn get_datasheet_measurement_expectations() -> Vec<I2cTransaction> {
+    let mut expectations = (0xAA..=0xBF)
+        .zip(CALIBRATION)
+        .map(|(reg, value)| I2cTransaction::write_read(0x77, vec![reg], vec![value]))
+        .collect::<Vec<_>>();
+    expectations.extend([
+        I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+        I2cTransaction::write_read(0x77, vec![0xF6], vec![0x6C]),
+        I2cTransaction::write_read(0x77, vec![0xF7], vec![0xFA]),
+        I2cTransaction::write(0x77, vec![0xF4, 0x34]),
+        I2cTransaction::write_read(0x77, vec![0xF6], vec![0x5D]),
+        I2cTransaction::write_read(0x77, vec![0xF7], vec![0x23]),
+        I2cTransaction::write_read(0x77, vec![0xF8], vec![0x00]),
+    ]);
+    expectations
+}
+
+#[test]
+fn read_pressure_altitude_ignores_sea_level_pressure() {
+    let expectations = get_datasheet_measurement_expectations();
+    let mut i2c = I2cMock::new(&expectations);
+    let config = Config {
+        sea_level_pressure: 70_000,
+        ..Default::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, config).init().unwrap();
+    let altitude = bmp.read_pressure_altitude().unwrap();
+
+    assert!((altitude - 3017.0).abs() < 1.0);
+    i2c.done();
+}
+
+#[test]
+fn read_density_altitude_ok_given_readings() {
+    let expectations = get_datasheet_measurement_expectations();
+    let mut i2c = I2cMock::new(&expectations);
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+    let altitude = bmp.read_density_altitude().unwrap();
+
+    // About 20 ºC above the standard atmosphere's temperature at the pressure altitude of 3017 m
+    assert!((altitude - 3694.0).abs() < 10.0);
+    i2c.done();
+}
+
+#[test]
+fn read_air_density_ok_given_readings() {
+    let expectations = get_datasheet_measurement_expectations();
+    let mut i2c = I2cMock::new(&expectations);
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+    let density = bmp.read_air_density().unwrap();
+
+    assert!((density - 0.8458).abs() < 0.001);
+    i2c.done();
+}
+
+#[test]
+fn read_measurement_with_floating_point_compensation() {
+    let expectations = get_datasheet_measurement_expectations();
+    let mut i2c = I2cMock::new(&expectations);
+    let config = Config {
+        compensation: CompensationAlgorithm::FloatingPoint,
+        ..Default::default()
+    };
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, config).init().unwrap();
+    let measurement = bmp.read_measurement().unwrap();
+
+    assert!((measurement.temperature - 15.0).abs() < 0.01);
+    assert!((measurement.pressure - 69964).abs() <= 2);
+    i2c.done();
+}
+
 #[test]
 fn read_altitude_fails_if_i2c_error() {
     let expectations = [
@@ -226,11 +979,18 @@ fn read_altitude_fails_if_i2c_error() {
     ]
     .concat();
     let mut i2c = I2cMock::new(&expectations);
-    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
-
-    bmp.init().unwrap();
+    let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default())
+        .init()
+        .unwrap();
 
-    assert_eq!(bmp.read_pressure(), Err(BMPError::I2C(ErrorKind::Other)));
+    assert_eq!(
+        bmp.read_pressure(),
+        Err(BMPError::I2C {
+            operation: Operation::Pressure,
+            register: 0xF6,
+            source: ErrorKind::Other
+        })
+    );
     i2c.done();
 }
 
@@ -250,6 +1010,236 @@ fn soft_reset_fails_if_i2c_error() {
     let mut i2c = I2cMock::new(&expectations);
     let mut bmp = BMP::new(i2c.clone(), NoopDelay, Default::default());
 
-    assert_eq!(bmp.soft_reset(), Err(BMPError::I2C(ErrorKind::Other)));
+    assert_eq!(
+        bmp.soft_reset(),
+        Err(BMPError::I2C {
+            operation: Operation::Reset,
+            register: 0xE0,
+            source: ErrorKind::Other
+        })
+    );
+    i2c.done();
+}
+
+#[test]
+fn probe_finds_device() {
+    let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+    let expectations = [
+        I2cTransaction::write_read(0x77, vec![0xD0], vec![0x55]),
+        I2cTransaction::write_read(0x77, vec![0xD0], vec![0x58]),
+        I2cTransaction::write_read(0x77, vec![0xD0], vec![0x00]).with_error(nack),
+        I2cTransaction::write_read(0x77, vec![0xD0], vec![0x00]).with_error(ErrorKind::Bus),
+    ];
+    let mut i2c = I2cMock::new(&expectations);
+
+    assert_eq!(probe(&mut i2c, 0x77), Ok(true));
+    assert_eq!(probe(&mut i2c, 0x77), Ok(false));
+    assert_eq!(probe(&mut i2c, 0x77), Ok(false));
+    assert_eq!(probe(&mut i2c, 0x77), Err(ErrorKind::Bus));
+    i2c.done();
+}
+
+#[test]
+fn scan_mux_finds_devices_on_channels() {
+    let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+    let mut expectations = Vec::new();
+    for channel in 0..8 {
+        expectations.push(I2cTransaction::write(0x70, vec![1 << channel]));
+        let probe = I2cTransaction::write_read(0x77, vec![0xD0], vec![0x55]);
+        expectations.push(match channel {
+            1 | 6 => probe,
+            _ => probe.with_error(nack),
+        });
+    }
+    expectations.push(I2cTransaction::write(0x70, vec![0]));
+    let mut i2c = I2cMock::new(&expectations);
+
+    assert_eq!(scan_mux(&mut i2c, 0x70, 0x77), Ok(0b0100_0010));
+    i2c.done();
+}
+
+/// Expectations for initializing the driver of a device at `address`, checking its identifier first.
+fn get_array_init_expectations(address: u8) -> Vec<I2cTransaction> {
+    [I2cTransaction::write_read(address, vec![0xD0], vec![0x55])]
+        .into_iter()
+        .chain((0xAA..=0xBF).map(|reg| I2cTransaction::write_read(address, vec![reg], vec![0x12])))
+        .collect()
+}
+
+/// Drivers of two devices at different addresses, sharing one mocked bus.
+fn new_array_drivers(
+    i2c: &I2cMock,
+    delay: &RecordingDelay,
+    timing: ConversionTiming,
+) -> [BMP<I2cMock, RecordingDelay, Uninitialized>; 2] {
+    [0x77, 0x76].map(|address| {
+        let config = Config {
+            address,
+            timing,
+            ..Config::default()
+        };
+        BMP::new(i2c.clone(), delay.clone(), config)
+    })
+}
+
+fn get_array_temperature_expectations(address: u8) -> Vec<I2cTransaction> {
+    vec![
+        I2cTransaction::write_read(address, vec![0xF6], vec![0xFF]),
+        I2cTransaction::write_read(address, vec![0xF7], vec![0xFF]),
+    ]
+}
+
+fn get_array_pressure_expectations(address: u8) -> Vec<I2cTransaction> {
+    vec![
+        I2cTransaction::write_read(address, vec![0xF6], vec![0x33]),
+        I2cTransaction::write_read(address, vec![0xF7], vec![0x38]),
+        I2cTransaction::write_read(address, vec![0xF8], vec![0x00]),
+    ]
+}
+
+fn get_array_expected_measurement(array: &BmpArray<I2cMock, RecordingDelay, 2>) -> Measurement {
+    compensate(
+        &array.calibration_data()[0],
+        RawSample {
+            ut: 0xFFFF,
+            up: 0x3338,
+            oss: Oss::LowPower,
+        },
+    )
+    .unwrap()
+}
+
+#[test]
+fn bmp_array_reads_synchronized_measurements() {
+    let expectations = [
+        get_array_init_expectations(0x77),
+        get_array_init_expectations(0x76),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write(0x76, vec![0xF4, 0x2E]),
+        ],
+        get_array_temperature_expectations(0x77),
+        get_array_temperature_expectations(0x76),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x34]),
+            I2cTransaction::write(0x76, vec![0xF4, 0x34]),
+        ],
+        get_array_pressure_expectations(0x77),
+        get_array_pressure_expectations(0x76),
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let delay = RecordingDelay::default();
+    let drivers = new_array_drivers(&i2c, &delay, ConversionTiming::DATASHEET_MAX);
+    let mut array = BmpArray::init(drivers).unwrap();
+    let expected = get_array_expected_measurement(&array);
+    let [first, second] = array.read();
+
+    assert_eq!(first, Ok(expected));
+    assert_eq!(second, Ok(expected));
+    // Conversions of both devices overlap, so each is waited for only once
+    assert_eq!(delay.total_us(), 4500 + 4500);
+    assert!(array
+        .drivers()
+        .iter()
+        .all(|driver| driver.health().measurements == 1));
+    i2c.done();
+}
+
+#[test]
+fn bmp_array_polls_sco_of_each_device() {
+    let busy = |address| I2cTransaction::write_read(address, vec![0xF4], vec![0x2E]);
+    let done = |address| I2cTransaction::write_read(address, vec![0xF4], vec![0x0E]);
+    let expectations = [
+        get_array_init_expectations(0x77),
+        get_array_init_expectations(0x76),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]),
+            I2cTransaction::write(0x76, vec![0xF4, 0x2E]),
+            busy(0x77),
+            done(0x77),
+            done(0x76),
+        ],
+        get_array_temperature_expectations(0x77),
+        get_array_temperature_expectations(0x76),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x34]),
+            I2cTransaction::write(0x76, vec![0xF4, 0x34]),
+            done(0x77),
+            busy(0x76),
+            busy(0x76),
+            done(0x76),
+        ],
+        get_array_pressure_expectations(0x77),
+        get_array_pressure_expectations(0x76),
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let delay = RecordingDelay::default();
+    let drivers = new_array_drivers(&i2c, &delay, ConversionTiming::polling(1000));
+    let mut array = BmpArray::init(drivers).unwrap();
+    let expected = get_array_expected_measurement(&array);
+
+    assert_eq!(array.read(), [Ok(expected), Ok(expected)]);
+    // Outputs are only read once each device's SCO bit clears
+    assert_eq!(delay.total_us(), 1000 + 2000);
+    i2c.done();
+}
+
+#[test]
+fn bmp_array_init_fails_given_invalid_id() {
+    let expectations = [
+        get_array_init_expectations(0x77),
+        vec![I2cTransaction::write_read(0x76, vec![0xD0], vec![0xFF])],
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let drivers = new_array_drivers(
+        &i2c,
+        &RecordingDelay::default(),
+        ConversionTiming::DATASHEET_MAX,
+    );
+
+    assert!(matches!(
+        BmpArray::init(drivers),
+        Err(BMPError::InvalidDeviceId(0xFF))
+    ));
+    i2c.done();
+}
+
+#[test]
+fn bmp_array_failure_only_affects_one_device() {
+    let expectations = [
+        get_array_init_expectations(0x77),
+        get_array_init_expectations(0x76),
+        vec![
+            I2cTransaction::write(0x77, vec![0xF4, 0x2E]).with_error(ErrorKind::Other),
+            I2cTransaction::write(0x76, vec![0xF4, 0x2E]),
+        ],
+        get_array_temperature_expectations(0x76),
+        vec![I2cTransaction::write(0x76, vec![0xF4, 0x34])],
+        get_array_pressure_expectations(0x76),
+    ]
+    .concat();
+    let mut i2c = I2cMock::new(&expectations);
+    let drivers = new_array_drivers(
+        &i2c,
+        &RecordingDelay::default(),
+        ConversionTiming::DATASHEET_MAX,
+    );
+    let mut array = BmpArray::init(drivers).unwrap();
+    let [first, second] = array.read();
+
+    assert_eq!(
+        first,
+        Err(BMPError::I2C {
+            operation: Operation::Temperature,
+            register: 0xF4,
+            source: ErrorKind::Other
+        })
+    );
+    assert!(second.is_ok());
+    assert_eq!(array.drivers()[0].health().consecutive_failures, 1);
+    assert_eq!(array.drivers()[1].health().consecutive_failures, 0);
     i2c.done();
 }
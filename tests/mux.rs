@@ -0,0 +1,167 @@
+#![cfg(not(feature = "async"))]
+
+mod common;
+
+use bmp085_180_rs::mux::Tca9548a;
+use bmp085_180_rs::scan::scan_mux;
+use bmp085_180_rs::{BMPError, BmpArray, Operation, BMP};
+use common::{SimBus, SimDevice, BMP_ADDRESS, MUX_ADDRESS, UT};
+use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+use embedded_hal_mock::eh1::delay::NoopDelay;
+
+#[test]
+fn scan_finds_simulated_devices() {
+    let mut bus = SimBus::new([
+        None,
+        Some(SimDevice::datasheet()),
+        None,
+        None,
+        Some(SimDevice::datasheet()),
+        None,
+        None,
+        None,
+    ]);
+
+    assert_eq!(
+        scan_mux(&mut bus, MUX_ADDRESS, BMP_ADDRESS),
+        Ok(0b0001_0010)
+    );
+    assert_eq!(bus.enabled, 0);
+}
+
+#[test]
+fn each_channel_reaches_its_device() {
+    let mux = Tca9548a::new(
+        SimBus::new([
+            None,
+            None,
+            Some(SimDevice::datasheet()),
+            Some(SimDevice::new(30000, 25000)),
+            None,
+            None,
+            None,
+            None,
+        ]),
+        MUX_ADDRESS,
+    );
+    let mut first = BMP::new(mux.channel(2), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+    let mut second = BMP::new(mux.channel(3), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+
+    assert_eq!(first.read_raw_temperature(), Ok(UT));
+    assert_eq!(second.read_raw_temperature(), Ok(30000));
+    assert_eq!(first.read_temperature(), Ok(15.0));
+    assert_eq!(first.read_pressure(), Ok(69964));
+    assert_eq!(
+        second.read_raw_pressure().map(|sample| sample.up),
+        Ok(25000)
+    );
+}
+
+#[test]
+fn channel_is_only_selected_when_switching() {
+    let mux = Tca9548a::new(
+        SimBus::new([
+            Some(SimDevice::datasheet()),
+            Some(SimDevice::datasheet()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ]),
+        MUX_ADDRESS,
+    );
+    let mut first = BMP::new(mux.channel(0), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+    let mut second = BMP::new(mux.channel(1), NoopDelay, Default::default())
+        .init()
+        .unwrap();
+
+    assert!(first.read_pressure().is_ok());
+    assert!(first.read_pressure().is_ok());
+    assert!(second.read_pressure().is_ok());
+    // Initialization of each device, then switching back to the first and to the second one
+    assert_eq!(mux.release().mux_writes, 4);
+}
+
+#[test]
+fn channel_without_device_is_not_acknowledged() {
+    let mux = Tca9548a::new(
+        SimBus::new([
+            Some(SimDevice::datasheet()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ]),
+        MUX_ADDRESS,
+    );
+    let mut bmp = BMP::new(mux.channel(5), NoopDelay, Default::default());
+
+    assert_eq!(
+        bmp.test_connection(),
+        Err(BMPError::I2C {
+            operation: Operation::Connect,
+            register: 0xD0,
+            source: ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+        })
+    );
+}
+
+#[test]
+fn failed_selection_is_retried_on_next_transaction() {
+    let mut bus = SimBus::new([
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(SimDevice::datasheet()),
+        None,
+    ]);
+    bus.failing_mux_writes = 1;
+    let mux = Tca9548a::new(bus, MUX_ADDRESS);
+    let mut bmp = BMP::new(mux.channel(6), NoopDelay, Default::default());
+
+    assert!(bmp.test_connection().is_err());
+    assert_eq!(bmp.test_connection(), Ok(()));
+    assert_eq!(mux.release().mux_writes, 2);
+}
+
+#[test]
+fn bmp_array_measures_devices_on_channels() {
+    let mux = Tca9548a::new(
+        SimBus::new([
+            None,
+            None,
+            Some(SimDevice::datasheet()),
+            None,
+            None,
+            Some(SimDevice::datasheet()),
+            None,
+            None,
+        ]),
+        MUX_ADDRESS,
+    );
+    let mut array = BmpArray::init([
+        BMP::new(mux.channel(2), NoopDelay, Default::default()),
+        BMP::new(mux.channel(5), NoopDelay, Default::default()),
+    ])
+    .unwrap();
+    let [first, second] = array.read();
+
+    assert_eq!(first.map(|measurement| measurement.pressure), Ok(69964));
+    assert_eq!(second.map(|measurement| measurement.pressure), Ok(69964));
+    // Initialization of each device, then switching channels for each step of both conversions
+    assert_eq!(mux.release().mux_writes, 2 + 8);
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use bmp085_180_rs::mux::Tca9548a;
+use bmp085_180_rs::{BmpArray, BMP};
+use common::{RecordingDelay, SimBus, SimDevice, MEASUREMENT, MUX_ADDRESS, UP, UT};
+use embassy_futures::block_on;
+use embassy_futures::join::join;
+
+fn new_mux() -> Tca9548a<SimBus> {
+    Tca9548a::new(
+        SimBus::new([
+            Some(SimDevice::datasheet()),
+            Some(SimDevice::new(30000, 25000)),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ]),
+        MUX_ADDRESS,
+    )
+}
+
+#[test]
+fn channels_are_used_concurrently() {
+    let mux = new_mux();
+
+    block_on(async {
+        let (first, second) = join(
+            BMP::new(
+                mux.channel(0),
+                RecordingDelay::default(),
+                Default::default(),
+            )
+            .init(),
+            BMP::new(
+                mux.channel(1),
+                RecordingDelay::default(),
+                Default::default(),
+            )
+            .init(),
+        )
+        .await;
+        let (mut first, mut second) = (first.unwrap(), second.unwrap());
+        // Transactions of both drivers interleave, each waiting for the bus while the other one holds it
+        let (first, second) = join(first.read_raw_pressure(), second.read_raw_pressure()).await;
+
+        assert_eq!(first.map(|sample| (sample.ut, sample.up)), Ok((UT, UP)));
+        assert_eq!(
+            second.map(|sample| (sample.ut, sample.up)),
+            Ok((30000, 25000))
+        );
+    });
+}
+
+#[test]
+fn bmp_array_measures_devices_on_channels() {
+    let mux = new_mux();
+    let drivers = [0, 1].map(|channel| {
+        BMP::new(
+            mux.channel(channel),
+            RecordingDelay::default(),
+            Default::default(),
+        )
+    });
+
+    block_on(async {
+        let mut array = BmpArray::init(drivers).await.unwrap();
+        let [first, second] = array.read().await;
+
+        assert_eq!(first, Ok(MEASUREMENT));
+        assert!(second.is_ok_and(|measurement| measurement != MEASUREMENT));
+    });
+}
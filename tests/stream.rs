@@ -0,0 +1,99 @@
+mod common;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use bmp085_180_rs::BMP;
+use common::{RecordingDelay, SimDevice, MEASUREMENT};
+use embassy_futures::block_on;
+use futures_util::StreamExt;
+
+fn new_bmp(delay: RecordingDelay) -> BMP<SimDevice, RecordingDelay> {
+    block_on(BMP::new(SimDevice::datasheet(), delay, Default::default()).init()).unwrap()
+}
+
+/// Simulated clock, advanced by the driver's delays and by the time spent by the consumer.
+fn clock(delay: &RecordingDelay, busy_us: &Rc<Cell<u64>>) -> impl FnMut() -> u64 {
+    let (delay, busy_us) = (delay.clone(), busy_us.clone());
+    move || delay.total_us() + busy_us.get()
+}
+
+#[test]
+fn stream_waits_for_the_rest_of_each_period() {
+    let delay = RecordingDelay::default();
+    let busy_us = Rc::default();
+    let mut bmp = new_bmp(delay.clone());
+
+    block_on(async {
+        let mut measurements = core::pin::pin!(bmp.stream(100_000, clock(&delay, &busy_us)));
+        for _ in 0..3 {
+            assert_eq!(measurements.next().await, Some(Ok(MEASUREMENT)));
+        }
+    });
+    // Three conversions of 4.5 ms each for temperature and pressure, and the rest of two periods
+    assert_eq!(delay.total_us(), 3 * 9_000 + 2 * 91_000);
+    assert_eq!(bmp.health().measurements, 3);
+}
+
+#[test]
+fn stream_waits_for_periods_longer_than_35_minutes() {
+    let delay = RecordingDelay::default();
+    let busy_us = Rc::default();
+    let mut bmp = new_bmp(delay.clone());
+
+    block_on(async {
+        let measurements = bmp.stream(3_000_000_000, clock(&delay, &busy_us)).take(2);
+        assert_eq!(measurements.count().await, 2);
+    });
+    // Beyond 2^31 µs, which the deadlines must not mistake for the past
+    assert_eq!(delay.total_us(), 3_000_000_000 + 9_000);
+}
+
+#[test]
+fn stream_does_not_wait_if_period_is_shorter_than_conversions() {
+    let delay = RecordingDelay::default();
+    let busy_us = Rc::default();
+    let mut bmp = new_bmp(delay.clone());
+
+    block_on(async {
+        let measurements = bmp.stream(1_000, clock(&delay, &busy_us)).take(2);
+        assert_eq!(
+            measurements.collect::<Vec<_>>().await,
+            vec![Ok(MEASUREMENT), Ok(MEASUREMENT)]
+        );
+    });
+    assert_eq!(delay.total_us(), 2 * 9_000);
+}
+
+#[test]
+fn stream_skips_periods_missed_by_slow_consumer() {
+    let delay = RecordingDelay::default();
+    let busy_us = Rc::<Cell<u64>>::default();
+    let now_us = clock(&delay, &busy_us);
+    let mut bmp = new_bmp(delay.clone());
+    let mut received_us = Vec::new();
+
+    block_on(async {
+        let mut measurements = core::pin::pin!(bmp.stream(100_000, now_us));
+        for consumer_us in [250_000, 0, 0] {
+            assert_eq!(measurements.next().await, Some(Ok(MEASUREMENT)));
+            received_us.push(delay.total_us() + busy_us.get());
+            busy_us.set(busy_us.get() + consumer_us);
+        }
+    });
+    // The measurement due at 100 ms is skipped, the one due at 200 ms is taken late at 259 ms,
+    // and the next one is back on schedule at 300 ms
+    assert_eq!(received_us, vec![9_000, 268_000, 309_000]);
+    assert_eq!(bmp.health().measurements, 3);
+}
+
+#[test]
+fn stream_is_lazy() {
+    let delay = RecordingDelay::default();
+    let mut bmp = new_bmp(delay.clone());
+
+    let measurements = bmp.stream(100_000, || 0);
+    drop(measurements);
+    assert_eq!(delay.total_us(), 0);
+    assert_eq!(bmp.health().measurements, 0);
+}